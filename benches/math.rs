@@ -0,0 +1,54 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use raster::*;
+
+#[rustfmt::skip]
+fn matrix() -> Matrix4 {
+    Matrix4::new([
+        0.8, -0.2, 0.4, 1.0,
+        0.3, 0.9, -0.1, 2.0,
+        -0.4, 0.2, 0.7, 3.0,
+        0.0, 0.0, 0.0, 1.0,
+    ])
+}
+
+fn matrix4(c: &mut Criterion) {
+    let a = matrix();
+    let b = Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 0.7).get_rotation();
+    let v = Vec4::new(1.0, 2.0, 3.0, 1.0);
+
+    c.bench_function("matrix4 * matrix4", |bench| {
+        bench.iter(|| black_box(a) * black_box(b))
+    });
+    c.bench_function("matrix4 * vec4", |bench| {
+        bench.iter(|| black_box(a) * black_box(v))
+    });
+    c.bench_function("matrix4 inv", |bench| bench.iter(|| black_box(a).inv()));
+    c.bench_function("matrix4 det", |bench| bench.iter(|| black_box(a).det()));
+}
+
+fn quat(c: &mut Criterion) {
+    let a = Quat::from_axis_angle(Vec3::new(1.0, 2.0, 3.0), 0.7);
+    let b = Quat::from_axis_angle(Vec3::new(-1.0, 0.5, 2.0), 1.3);
+
+    c.bench_function("quat * quat", |bench| {
+        bench.iter(|| black_box(a) * black_box(b))
+    });
+    c.bench_function("quat from_axis_angle", |bench| {
+        bench.iter(|| Quat::from_axis_angle(black_box(Vec3::Y), black_box(0.3)))
+    });
+    c.bench_function("quat get_rotation", |bench| {
+        bench.iter(|| black_box(a).get_rotation())
+    });
+    c.bench_function("quat look_at", |bench| {
+        bench.iter(|| {
+            look_at(
+                black_box(Vec3::new(0.0, 1.0, 3.0)),
+                black_box(Vec3::zero()),
+                Vec3::Y,
+            )
+        })
+    });
+}
+
+criterion_group!(benches, matrix4, quat);
+criterion_main!(benches);
@@ -0,0 +1,126 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use raster::*;
+
+const AMBIENT_LIGHT_INTENSITY: f32 = 0.2;
+
+fn render(width: u32, height: u32, mesh: &Mesh) -> Render {
+    let (min, max) = mesh.get_bounding();
+    let target = 0.5 * (min + max);
+    let frustum = Frustum::new(
+        1.0,
+        1000.0,
+        60.0f32.to_radians(),
+        width as f32 / height as f32,
+    );
+    let camera = Camera::new(frustum, target + Vec3::new(0.0, 0.0, 2.0), target);
+    Render::new(width, height, camera, Shader::standard())
+}
+
+fn red_ball() -> Mesh {
+    let (vertexes, indices) = load_model("assets/RedBall.obj");
+    let material = Material::new(
+        PhongMaterial::default(),
+        BaseColor::Color([255, 0, 0]),
+        None,
+        None,
+    );
+    Mesh::new(vertexes, indices, material)
+}
+
+fn diablo3() -> Mesh {
+    let (vertexes, indices) = load_model("assets/diablo3_pose.obj");
+    let material = Material::new(
+        PhongMaterial::default(),
+        BaseColor::Map(BaseColorMap::new("assets/diablo3_pose_diffuse.bmp")),
+        None,
+        None,
+    );
+    Mesh::new(vertexes, indices, material)
+}
+
+fn draw(c: &mut Criterion) {
+    let lights = LightSet::new(Vec3::new(
+        AMBIENT_LIGHT_INTENSITY,
+        AMBIENT_LIGHT_INTENSITY,
+        AMBIENT_LIGHT_INTENSITY,
+    ))
+    .with(PointLight::new(Vec3::new(-5.0, 5.0, 5.0), 200.0));
+    let mut group = c.benchmark_group("draw");
+    group.sample_size(20);
+    for (name, mesh) in [("RedBall", red_ball()), ("diablo3", diablo3())] {
+        for (width, height) in [(320, 240), (640, 480), (1024, 720)] {
+            let mut render = render(width, height, &mesh);
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("{}x{}", width, height)),
+                &mesh,
+                |bench, mesh| {
+                    bench.iter(|| {
+                        render.reset();
+                        render.draw(mesh, &lights, Matrix4::ident())
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn raster_triangle(c: &mut Criterion) {
+    let mesh = red_ball();
+    let mut render = render(1024, 1024, &mesh);
+    let vertex =
+        |x: f32, y: f32| Uniform::new(Vec3::zero(), Vec3::Z, Vec3::new(x, y, 0.0), Vec2::default());
+
+    let mut group = c.benchmark_group("raster_triangle");
+    for size in [4.0, 16.0, 64.0, 256.0, 1000.0] {
+        let triangle = Triangle::new(
+            vertex(10.0, 10.0),
+            vertex(10.0 + size, 10.0),
+            vertex(10.0, 10.0 + size),
+        );
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size),
+            &triangle,
+            |bench, triangle| bench.iter(|| render.raster_triangle(black_box(triangle))),
+        );
+    }
+    group.finish();
+}
+
+fn texture(c: &mut Criterion) {
+    let texture = Texture::load("assets/diablo3_pose_diffuse.bmp");
+    c.bench_function("texture get_pixel", |bench| {
+        bench.iter(|| texture.get_pixel(black_box(Vec2::new(0.3, 0.7))))
+    });
+}
+
+//the three algorithms on the same set of lines, criterion reports them side by side
+fn draw_line(c: &mut Criterion) {
+    let mesh = red_ball();
+    let mut render = render(1024, 1024, &mesh);
+    let lines = [
+        (Vec2::new(0.0, 0.0), Vec2::new(1000.0, 100.0)), //flat
+        (Vec2::new(0.0, 0.0), Vec2::new(1000.0, 1000.0)), //diagonal
+        (Vec2::new(500.0, 0.0), Vec2::new(400.0, 1000.0)), //steep
+        (Vec2::new(1000.0, 900.0), Vec2::new(0.0, 10.0)), //reversed
+    ];
+
+    let mut group = c.benchmark_group("draw_line");
+    for (name, algorithm) in [
+        ("DDA", DrawLineAlgorithm::DDA),
+        ("MiddlePoint", DrawLineAlgorithm::MiddlePoint),
+        ("Bresenham", DrawLineAlgorithm::Bresenham),
+    ] {
+        group.bench_function(name, |bench| {
+            bench.iter(|| {
+                lines.iter().for_each(|(start, end)| {
+                    render.draw_line(*start, *end, [255, 255, 255], algorithm)
+                })
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, draw, raster_triangle, texture, draw_line);
+criterion_main!(benches);
@@ -1,15 +1,52 @@
 use fltk::{
-    app::{self, event_key_down},
-    enums::{Key, Mode},
+    app::{self, event_key, event_key_down},
+    enums::{Event, Key, Mode},
     prelude::*,
     window::Window,
 };
 
 use raster::*;
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+    time::Instant,
+};
 
 const WIDTH: i32 = 1024;
 const HEIGHT: i32 = 720;
 const AMBIENT_LIGHT_INTENSITY: f32 = 0.2;
+
+//toggled by key press events, read by the draw callback
+#[derive(Clone, Copy)]
+struct ViewSettings {
+    render_mode: RenderMode,
+    debug_view: DebugView,
+    show_grid: bool,
+    show_bounding: bool,
+    show_normals: bool,
+    show_axes: bool,
+    pbr: bool,
+    toon: u32, //0 off, 1 cel shading, 2 hatching
+    outline: bool,
+    shading: ShadingModel,
+    compare_shading: bool,
+    deferred: bool,
+    orthographic: bool,
+    controller: u32, //0 arcball, 1 turntable, 2 fly
+    recording: bool,
+    playing: bool,
+    tone_mapping: ToneMapping,
+    exposure: f32,
+    dither: Dither,
+    ssao: bool,
+    fog: bool,
+    bloom: bool,
+    blur: bool,
+    grading: bool,
+    vignette: bool,
+    fxaa: bool,
+    changed: bool,
+}
 fn main() {
     let app = fltk::app::App::default();
     let mut window = Window::new(100, 100, WIDTH, HEIGHT, "raster");
@@ -25,7 +62,33 @@ fn main() {
         Some(normal),
         Some(specular),
     );
-    let mesh = Mesh::new(vertexes, indices, material);
+    let mut phong_mesh = Mesh::new(vertexes.clone(), indices.clone(), material);
+
+    //same model with the metallic-roughness material, toggled by 'p'
+    let material = Material::new(
+        PbrMaterial::new(Vec3::new(1.0, 1.0, 1.0), 0.3, 0.4)
+            .with_roughness_map(ScalarMap::new("assets/diablo3_pose_spec.bmp", 0)),
+        BaseColor::Map(BaseColorMap::new("assets/diablo3_pose_diffuse.bmp")),
+        Some(NormalMap::new("assets/diablo3_pose_nm.bmp")),
+        None,
+    );
+    let mut pbr_mesh = Mesh::new(vertexes.clone(), indices.clone(), material);
+
+    //cel shading and hatching, cycled by 'h'
+    let material = Material::new(
+        ToonMaterial::new(ToonRamp::Bands(3)),
+        BaseColor::Map(BaseColorMap::new("assets/diablo3_pose_diffuse.bmp")),
+        Some(NormalMap::new("assets/diablo3_pose_nm.bmp")),
+        None,
+    );
+    let mut toon_mesh = Mesh::new(vertexes.clone(), indices.clone(), material);
+    let material = Material::new(
+        ToonMaterial::default().with_hatching(TonalArtMap::generate(128, 6).with_scale(4.0)),
+        BaseColor::Color([255, 255, 255]),
+        Some(NormalMap::new("assets/diablo3_pose_nm.bmp")),
+        None,
+    );
+    let mut hatching_mesh = Mesh::new(vertexes, indices, material);
 
     // //red ball
     // let (vertexes, indices) = load_model("assets/RedBall.obj");
@@ -37,7 +100,7 @@ fn main() {
     // );
     // let mesh = Mesh::new(vertexes, indices, material);
 
-    let bounding = mesh.get_bounding();
+    let bounding = phong_mesh.get_bounding();
     let target: Vec3 = 0.5 * (bounding.0 + bounding.1);
     let frustum = Frustum::new(
         1.0,
@@ -46,102 +109,308 @@ fn main() {
         WIDTH as f32 / HEIGHT as f32,
     );
     let camera = Camera::new(frustum, Vec3::new(0.0, 0.0, 2.0), target);
+    //left drag rotates, right drag pans, the wheel dollies. the fly camera moves with w/a/s/d/q/e
+    let controller = Rc::new(RefCell::new(new_controller(0, &camera, target)));
 
-    let vertex_shader = Box::new(|vertex: &Vertex, mvp: &Matrix4, model_mat: &Matrix4| {
-        let world_position = model_mat
-            .mul(vertex.position.upgrade())
-            .perspective_divide();
-        let world_normal = model_mat
-            .inv()
-            .unwrap()
-            .transpose()
-            .mul(vertex.normal.upgrade())
-            .perspective_divide();
-
-        let ndc_position = mvp.mul(vertex.position.upgrade()).perspective_divide();
-        Uniform::new(world_position, world_normal, ndc_position, vertex.texcoord)
-    });
-    let fragment_shader = Box::new(
-        |fragment: &Fragment,
-         material: &Material,
-         light: &PointLight,
-         camera_position: &Vec3,
-         model_mat: &Matrix4| {
-            let n = if let Some(normal_map) = &material.normal {
-                let model_normal = normal_map.get_normal(fragment.texcoord);
-                model_mat
-                    .inv()
-                    .unwrap()
-                    .transpose()
-                    .mul(model_normal.upgrade())
-                    .perspective_divide()
-                    .normalize()
-            } else {
-                fragment.world_normal.normalize()
-            };
-            let l = (fragment.world_position - light.position).normalize();
-            let length2 = (fragment.world_position - light.position).length2();
-            let h = ((fragment.world_position - *camera_position).normalize() + l).normalize();
-
-            let ambient_intensity = AMBIENT_LIGHT_INTENSITY * material.material.ambient;
-            let diffuse_intensity =
-                (light.intensity / length2) * n.dot(&l).max(0.0) * material.material.diffuse;
-            let specular = if let Some(specular_map) = &material.specular {
-                specular_map.get_specular(fragment.texcoord)
-            } else {
-                material.material.specular
-            };
-
-            let specular_intensity = (light.intensity / length2)
-                * (n.dot(&h).max(0.0)).powf(material.material.specular_shininess)
-                * specular;
-
-            let light_intensity = ambient_intensity + diffuse_intensity + specular_intensity;
-            let base_color = match &material.base {
-                BaseColor::Color(color) => (*color).into(),
-                BaseColor::Map(map) => map.get_color(fragment.texcoord),
-            };
-            let color = [
-                (light_intensity.x * base_color.x * 255.0) as u8,
-                (light_intensity.y * base_color.y * 255.0) as u8,
-                (light_intensity.z * base_color.z * 255.0) as u8,
-            ];
-
-            ShadedFragment::new(fragment.screen_pos, fragment.depth, color)
-        },
-    );
-    let shader = Shader::new(vertex_shader, fragment_shader);
+    //phong or cook-torrance depending on the material, custom closures can be passed to Shader::new
+    let shader = Shader::standard();
     let mut render = Render::new(WIDTH as u32, HEIGHT as u32, camera, shader);
+    render.set_hdr(true);
 
-    let light = PointLight::new(Vec3::new(-5.0, 5.0, 5.0), 200.0);
+    //key light, blue fill light and a warm sun
+    let mut lights = LightSet::new(Vec3::new(
+        AMBIENT_LIGHT_INTENSITY,
+        AMBIENT_LIGHT_INTENSITY,
+        AMBIENT_LIGHT_INTENSITY,
+    ))
+    .with(PointLight::new(Vec3::new(-5.0, 5.0, 5.0), 200.0))
+    .with(
+        PointLight::new(Vec3::new(4.0, 1.0, 3.0), 20.0)
+            .with_color(Vec3::new(0.5, 0.6, 1.0))
+            .with_attenuation(Attenuation::Range(10.0)),
+    )
+    .with(
+        DirectionalLight::new(Vec3::new(0.5, -1.0, -0.5), 0.4).with_color(Vec3::new(1.0, 0.9, 0.7)),
+    );
+    //cargo run --example draw_mesh -- path/to/environment.hdr
+    if let Some(path) = std::env::args().nth(1) {
+        lights = lights.with_environment(Ibl::load(&path));
+        render.set_background(EnvironmentMap::load(&path));
+    } else {
+        render.set_background(Background::Gradient {
+            top: [40, 44, 52],
+            bottom: [12, 12, 16],
+        });
+    }
+    //cargo run --example draw_mesh -- environment.hdr grading.cube
+    let color_lut = std::env::args().nth(2).map(|path| ColorLut::load(&path));
     let mut dirty = true;
-    window.draw(move |_| {
-        // event handle
-        {
-            //rotation
-            let camera = render.get_camera();
-            if event_key_down(Key::from_char('a')) {
-                camera.rotation_around(target, Quat::from_axis_angle(Vec3::Y, -0.1));
-                dirty = true;
+    let mut frame = 0;
+    let settings = Rc::new(Cell::new(ViewSettings {
+        render_mode: RenderMode::Shaded,
+        debug_view: DebugView::None,
+        show_grid: false,
+        show_bounding: false,
+        show_normals: false,
+        show_axes: false,
+        pbr: false,
+        toon: 0,
+        outline: false,
+        shading: ShadingModel::Phong,
+        compare_shading: false,
+        deferred: false,
+        orthographic: false,
+        controller: 0,
+        recording: false,
+        playing: false,
+        tone_mapping: ToneMapping::Aces,
+        exposure: 1.0,
+        dither: Dither::BlueNoise,
+        ssao: false,
+        fog: false,
+        bloom: false,
+        blur: false,
+        grading: false,
+        vignette: false,
+        fxaa: false,
+        changed: true,
+    }));
+    let handle_settings = settings.clone();
+    let handle_controller = controller.clone();
+    window.handle(move |_, event| {
+        let input = |event: InputEvent| handle_controller.borrow_mut().handle(event);
+        let position = || {
+            cursor(
+                app::event_x() as f32,
+                app::event_y() as f32,
+                WIDTH as f32,
+                HEIGHT as f32,
+            )
+        };
+        let button = || match app::event_mouse_button() {
+            app::MouseButton::Left => MouseButton::Left,
+            app::MouseButton::Middle => MouseButton::Middle,
+            _ => MouseButton::Right,
+        };
+        match event {
+            Event::Focus => return true, //accept focus to receive key events
+            Event::Push => {
+                input(InputEvent::Press(button(), position()));
+                return true;
             }
-            if event_key_down(Key::from_char('d')) {
-                camera.rotation_around(target, Quat::from_axis_angle(Vec3::Y, 0.1));
-                dirty = true;
+            Event::Drag => {
+                input(InputEvent::Move(position()));
+                return true;
             }
-            if event_key_down(Key::from_char('s')) {
-                camera.rotation_around(target, Quat::from_axis_angle(Vec3::X, -0.1));
-                dirty = true;
+            Event::Released => {
+                input(InputEvent::Release(button()));
+                return true;
             }
-            if event_key_down(Key::from_char('w')) {
-                camera.rotation_around(target, Quat::from_axis_angle(Vec3::X, 0.1));
-                dirty = true;
+            Event::MouseWheel => {
+                match app::event_dy() {
+                    app::MouseWheel::Up => input(InputEvent::Scroll(1.0)),
+                    app::MouseWheel::Down => input(InputEvent::Scroll(-1.0)),
+                    _ => {}
+                }
+                return true;
+            }
+            Event::KeyUp => {
+                if let Some(movement) = movement(event_key()) {
+                    input(InputEvent::KeyUp(movement));
+                    return true;
+                }
+                return false;
+            }
+            Event::KeyDown => {
+                if let Some(movement) = movement(event_key()) {
+                    input(InputEvent::KeyDown(movement));
+                    return true;
+                }
             }
-            if event_key_down(Key::from_char('q')) {
-                camera.rotation_around(target, Quat::from_axis_angle(Vec3::Z, -0.1));
+            _ => return false,
+        }
+        let mut view = handle_settings.get();
+        match event_key().to_char() {
+            //shaded -> wireframe -> wireframe over shaded
+            Some('m') => {
+                view.render_mode = match view.render_mode {
+                    RenderMode::Shaded => RenderMode::Wireframe,
+                    RenderMode::Wireframe => RenderMode::ShadedWireframe,
+                    RenderMode::ShadedWireframe => RenderMode::Shaded,
+                }
+            }
+            //0: shaded, 1..7: depth/normal/uv/checker/overdraw/triangle id/back face
+            Some(key @ '0'..='7') => view.debug_view = DebugView::ALL[key as usize - '0' as usize],
+            Some('g') => view.show_grid = !view.show_grid,
+            Some('b') => view.show_bounding = !view.show_bounding,
+            Some('n') => view.show_normals = !view.show_normals,
+            Some('x') => view.show_axes = !view.show_axes,
+            Some('p') => view.pbr = !view.pbr,
+            Some('h') => view.toon = (view.toon + 1) % 3,
+            Some('y') => view.outline = !view.outline,
+            Some('.') => {
+                view.shading = match view.shading {
+                    ShadingModel::Flat => ShadingModel::Gouraud,
+                    ShadingModel::Gouraud => ShadingModel::Phong,
+                    ShadingModel::Phong => ShadingModel::Flat,
+                }
+            }
+            Some(',') => view.compare_shading = !view.compare_shading,
+            Some('j') => view.deferred = !view.deferred,
+            Some('/') => view.orthographic = !view.orthographic,
+            Some(';') => view.controller = (view.controller + 1) % 3,
+            //'[' starts and stops recording the camera, ']' loops the recording
+            Some('[') => view.recording = !view.recording,
+            Some(']') => view.playing = !view.playing,
+            Some('o') => {
+                view.tone_mapping = match view.tone_mapping {
+                    ToneMapping::Clamp => ToneMapping::Reinhard,
+                    ToneMapping::Reinhard => ToneMapping::Aces,
+                    ToneMapping::Aces => ToneMapping::Clamp,
+                }
+            }
+            Some('i') => {
+                view.dither = match view.dither {
+                    Dither::None => Dither::Ordered,
+                    Dither::Ordered => Dither::BlueNoise,
+                    Dither::BlueNoise => Dither::None,
+                }
+            }
+            //post effects
+            Some('c') => view.ssao = !view.ssao,
+            Some('z') => view.fog = !view.fog,
+            Some('l') => view.bloom = !view.bloom,
+            Some('u') => view.blur = !view.blur,
+            Some('k') => view.grading = !view.grading,
+            Some('v') => view.vignette = !view.vignette,
+            Some('f') => view.fxaa = !view.fxaa,
+            Some('-') => view.exposure *= 0.8,
+            Some('=') => view.exposure *= 1.25,
+            _ => return false,
+        }
+        view.changed = true;
+        handle_settings.set(view);
+        true
+    });
+    let mut active_controller = 0;
+    let mut last_update = Instant::now();
+    let mut camera_path = CameraPath::new();
+    let (mut recording, mut playing) = (false, false);
+    let mut record_start = Instant::now();
+    let mut path_playback = Playback::new(0.0);
+    window.draw(move |_| {
+        // event handle
+        {
+            let view = settings.get();
+            if view.changed {
+                if view.controller != active_controller {
+                    active_controller = view.controller;
+                    *controller.borrow_mut() =
+                        new_controller(active_controller, render.get_camera(), target);
+                }
+                if view.recording && !recording {
+                    camera_path.clear();
+                    record_start = Instant::now();
+                }
+                if !view.recording && recording {
+                    camera_path.save("camera.path");
+                    println!("{} camera keys saved to camera.path", camera_path.get_keys().len());
+                }
+                recording = view.recording;
+                if view.playing && !playing {
+                    path_playback =
+                        Playback::new(camera_path.get_duration()).with_mode(PlaybackMode::Loop);
+                }
+                //the controller takes over where the playback stopped
+                if !view.playing && playing {
+                    *controller.borrow_mut() =
+                        new_controller(active_controller, render.get_camera(), target);
+                }
+                playing = view.playing;
+                render.set_render_mode(view.render_mode);
+                render.set_debug_view(view.debug_view);
+                //r/t zoom both, in orthographic mode by changing the height of the view volume
+                let projection = render.get_camera().get_projection();
+                match (view.orthographic, projection) {
+                    (true, Projection::Perspective) => render
+                        .get_camera()
+                        .set_projection(Projection::Orthographic { height: 2.5 }),
+                    (false, Projection::Orthographic { .. }) => {
+                        render.get_camera().set_projection(Projection::Perspective)
+                    }
+                    _ => {}
+                }
+                render.set_output_transform(
+                    OutputTransform::default()
+                        .with_tone_mapping(view.tone_mapping)
+                        .with_exposure(view.exposure)
+                        .with_dither(view.dither),
+                );
+
+                //hdr passes first, grading and fxaa work best on the final colours
+                let mut post_stack = PostStack::new();
+                if view.ssao {
+                    post_stack.add(PostEffect::Ssao {
+                        radius: 0.2,
+                        intensity: 2.0,
+                        samples: 16,
+                    });
+                }
+                if view.fog {
+                    post_stack.add(PostEffect::Fog {
+                        color: Vec3::new(0.3, 0.32, 0.35),
+                        density: 0.2,
+                    });
+                }
+                if view.bloom {
+                    post_stack.add(PostEffect::Bloom {
+                        threshold: 1.0,
+                        intensity: 0.8,
+                        radius: 12,
+                    });
+                }
+                if view.blur {
+                    post_stack.add(PostEffect::Blur {
+                        kernel: BlurKernel::Gaussian,
+                        radius: 4,
+                    });
+                }
+                if view.outline {
+                    post_stack.add(PostEffect::Outline {
+                        color: Vec3::zero(),
+                        depth_threshold: 0.02,
+                        normal_threshold: 0.3,
+                    });
+                }
+                if let (true, Some(lut)) = (view.grading, &color_lut) {
+                    post_stack.add(PostEffect::ColorGrading(lut.clone()));
+                }
+                if view.vignette {
+                    post_stack.add(PostEffect::Vignette {
+                        intensity: 0.6,
+                        radius: 0.4,
+                    });
+                }
+                if view.fxaa {
+                    post_stack.add(PostEffect::Fxaa);
+                }
+                render.set_post_stack(post_stack);
+                settings.set(ViewSettings {
+                    changed: false,
+                    ..view
+                });
                 dirty = true;
             }
-            if event_key_down(Key::from_char('e')) {
-                camera.rotation_around(target, Quat::from_axis_angle(Vec3::Z, 0.1));
+
+            //rotation, panning and dolly, smoothed over the frames
+            let camera = render.get_camera();
+            let dt = last_update.elapsed().as_secs_f32();
+            last_update = Instant::now();
+            if playing {
+                path_playback.advance(dt);
+                camera_path.apply(path_playback.get_time(), camera);
+                dirty = true;
+            } else if controller.borrow_mut().update(camera, dt) {
                 dirty = true;
             }
 
@@ -154,11 +423,99 @@ fn main() {
                 camera.scale(0.1);
                 dirty = true;
             }
+            if recording {
+                camera_path.record(record_start.elapsed().as_secs_f32(), camera);
+            }
         }
 
         if dirty {
+            let start = Instant::now();
             render.reset();
-            render.draw(&mesh, &light, Matrix4::ident());
+            let view = settings.get();
+            let mesh = match (view.toon, view.pbr) {
+                (1, _) => &mut toon_mesh,
+                (2, _) => &mut hatching_mesh,
+                (_, true) => &mut pbr_mesh,
+                _ => &mut phong_mesh,
+            };
+            mesh.shading = view.shading;
+            let mut labels = vec![];
+            if view.compare_shading {
+                //flat, gouraud and phong next to each other
+                let width = bounding.1.x - bounding.0.x;
+                for (i, shading) in [
+                    ShadingModel::Flat,
+                    ShadingModel::Gouraud,
+                    ShadingModel::Phong,
+                ]
+                .into_iter()
+                .enumerate()
+                {
+                    let offset = Vec3::new((i as f32 - 1.0) * width, 0.0, 0.0);
+                    mesh.shading = shading;
+                    render.draw(mesh, &lights, Matrix4::translate(offset));
+                    let top = target + offset + Vec3::new(0.0, 0.6 * width, 0.0);
+                    if let Some(pos) = render.project(top) {
+                        labels.push((pos, format!("{:?}", shading)));
+                    }
+                }
+                mesh.shading = view.shading;
+            } else if view.deferred {
+                render.draw_deferred(mesh, Matrix4::ident());
+                render.shade_deferred(&lights, LightCulling::Tiled);
+            } else {
+                render.draw(mesh, &lights, Matrix4::ident());
+            }
+            if view.outline {
+                render.draw_outline(mesh, &Matrix4::ident(), [0, 0, 0], Some(1.0));
+            }
+
+            if view.show_grid {
+                render.draw_grid(bounding.0.y, 2.0, 0.25, [90, 90, 90], true);
+            }
+            if view.show_bounding {
+                render.draw_bounding_box(
+                    bounding.0,
+                    bounding.1,
+                    &Matrix4::ident(),
+                    [255, 255, 0],
+                    true,
+                );
+            }
+            if view.show_normals {
+                render.draw_normals(mesh, &Matrix4::ident(), 0.02, [0, 255, 255], true);
+            }
+            if view.show_axes {
+                render.draw_axes(&Matrix4::ident(), 1.0, false);
+            }
+            render.resolve();
+            let elapsed = start.elapsed().as_secs_f32();
+            frame += 1;
+
+            let stats = render.get_frame_stats();
+            let stats = format!(
+                "frame {}\n{:.1} ms {:.1} fps\ntriangles {}/{} fragments {} meshes culled {}\nvs {:.1} asm {:.1} raster {:.1} fs {:.1} merge {:.1} resolve {:.1} ms",
+                frame,
+                elapsed * 1000.0,
+                1.0 / elapsed,
+                stats.triangles_rasterized,
+                stats.triangles_assembled,
+                stats.fragments_generated,
+                stats.meshes_culled,
+                stats.vertex_shading_time.as_secs_f32() * 1000.0,
+                stats.assembly_time.as_secs_f32() * 1000.0,
+                stats.raster_time.as_secs_f32() * 1000.0,
+                stats.fragment_shading_time.as_secs_f32() * 1000.0,
+                stats.merge_time.as_secs_f32() * 1000.0,
+                stats.resolve_time.as_secs_f32() * 1000.0,
+            );
+            render.draw_text(Vec2::new(8.0, 8.0), &stats, [255, 255, 255], 2);
+            for (pos, label) in labels.iter() {
+                render.draw_text(*pos, label, [255, 255, 255], 2);
+            }
+            if let Some(pos) = render.project(target) {
+                render.draw_text(pos + Vec2::new(4.0, 4.0), "target", [255, 255, 0], 1);
+            }
         }
         dirty = false;
 
@@ -177,3 +534,23 @@ fn main() {
     //run event loop
     app.run().unwrap();
 }
+
+fn new_controller(kind: u32, camera: &Camera, target: Vec3) -> Box<dyn CameraController> {
+    match kind {
+        0 => Box::new(ArcballController::new(camera, target)),
+        1 => Box::new(TurntableController::new(camera, target)),
+        _ => Box::new(FlyController::new(camera)),
+    }
+}
+
+fn movement(key: Key) -> Option<Movement> {
+    match key.to_char()? {
+        'w' => Some(Movement::Forward),
+        's' => Some(Movement::Backward),
+        'a' => Some(Movement::Left),
+        'd' => Some(Movement::Right),
+        'e' => Some(Movement::Up),
+        'q' => Some(Movement::Down),
+        _ => None,
+    }
+}
@@ -0,0 +1,185 @@
+use raster::*;
+use std::f32::consts::PI;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+
+//cargo run --example scene -- out.png
+//cargo run --example scene -- frames/{}.png 48   renders the animation as an image sequence
+//cargo run --example scene -- frames/{}.png 48 camera.path   flies along a recorded camera path
+//diablo on a turntable, a red ball with a lamp orbiting it, the camera hangs on a rig
+fn main() {
+    let output = std::env::args().nth(1).unwrap_or("scene.png".to_string());
+    let frames: usize = std::env::args()
+        .nth(2)
+        .map_or(48, |frames| frames.parse().unwrap());
+
+    let mut scene = Scene::new(LightSet::new(Vec3::new(0.15, 0.15, 0.15)).with(
+        DirectionalLight::new(Vec3::new(0.5, -1.0, -0.5), 0.6).with_color(Vec3::new(1.0, 0.9, 0.7)),
+    ));
+
+    let (vertexes, indices) = load_model("assets/diablo3_pose.obj");
+    let diablo = scene.add_mesh(Mesh::new(
+        vertexes,
+        indices,
+        Material::new(
+            PhongMaterial::default(),
+            BaseColor::Map(BaseColorMap::new("assets/diablo3_pose_diffuse.bmp")),
+            Some(NormalMap::new("assets/diablo3_pose_nm.bmp")),
+            Some(SpecularMap::new("assets/diablo3_pose_spec.bmp")),
+        ),
+    ));
+    let (vertexes, indices) = load_model("assets/RedBall.obj");
+    let ball = scene.add_mesh(Mesh::new(
+        vertexes,
+        indices,
+        Material::new(
+            PbrMaterial::new(Vec3::new(1.0, 1.0, 1.0), 0.0, 0.3),
+            BaseColor::Color([255, 40, 30]),
+            None,
+            None,
+        ),
+    ));
+
+    let turntable = scene.add_node(
+        Node::new("turntable").with_rotation(Quat::from_axis_angle(Vec3::Y, PI / 6.0)),
+        None,
+    );
+    scene.add_node(Node::new("diablo").with_mesh(diablo), Some(turntable));
+    //children inherit the rotation of the orbit and the scale of the ball
+    let orbit = scene.add_node(
+        Node::new("orbit").with_rotation(Quat::from_axis_angle(Vec3::Y, -PI / 3.0)),
+        Some(turntable),
+    );
+    let red_ball = scene.add_node(
+        Node::new("red ball")
+            .with_translation(Vec3::new(1.0, 0.3, 0.0))
+            .with_scale(Vec3::new(0.5, 0.5, 0.5))
+            .with_mesh(ball),
+        Some(orbit),
+    );
+    scene.add_node(
+        Node::new("lamp")
+            .with_translation(Vec3::new(0.0, 0.6, 0.0))
+            .with_light(PointLight::new(Vec3::zero(), 8.0).with_color(Vec3::new(1.0, 0.4, 0.3))),
+        Some(red_ball),
+    );
+
+    let rig = scene.add_node(
+        Node::new("camera rig").with_rotation(Quat::from_axis_angle(Vec3::X, -PI / 12.0)),
+        None,
+    );
+    let camera = scene.add_node(
+        Node::new("camera")
+            .with_translation(Vec3::new(0.0, 0.0, 3.0))
+            .with_camera(Frustum::new(
+                0.1,
+                100.0,
+                50.0f32.to_radians(),
+                WIDTH as f32 / HEIGHT as f32,
+            )),
+        Some(rig),
+    );
+    scene.active_camera = Some(camera);
+
+    let placeholder = Camera::new(Frustum::new(0.1, 100.0, 1.0, 1.0), Vec3::Z, Vec3::zero());
+    let mut render = Render::new(WIDTH, HEIGHT, placeholder, Shader::standard());
+    render.set_hdr(true);
+    render.set_background(Background::Gradient {
+        top: [40, 44, 52],
+        bottom: [12, 12, 16],
+    });
+    render.set_output_transform(OutputTransform::default().with_tone_mapping(ToneMapping::Aces));
+
+    if let (true, Some(camera_path)) = (output.contains("{}"), std::env::args().nth(3)) {
+        let camera_path = CameraPath::load(&camera_path);
+        let mut playback = Playback::new(camera_path.get_duration());
+        //the last frame ends on the last key
+        let fps = (frames.max(2) - 1) as f32 / camera_path.get_duration().max(1e-3);
+        let stats = render.render_camera_path(
+            &mut scene,
+            &camera_path,
+            &mut playback,
+            frames,
+            fps,
+            &output,
+        );
+        println!(
+            "{} frames, {} triangles rasterized, {:.1} ms",
+            frames,
+            stats.triangles_rasterized,
+            stats.total_time().as_secs_f32() * 1000.0
+        );
+        return;
+    }
+    if output.contains("{}") {
+        let clip = animation(turntable, orbit, red_ball, rig);
+        let mut playback = Playback::new(clip.get_duration()).with_mode(PlaybackMode::Loop);
+        let stats = render.render_sequence(&mut scene, &clip, &mut playback, frames, 24.0, &output);
+        println!(
+            "{} frames, {} triangles rasterized, {:.1} ms",
+            frames,
+            stats.triangles_rasterized,
+            stats.total_time().as_secs_f32() * 1000.0
+        );
+        return;
+    }
+
+    render.reset();
+    let stats = render.draw_scene(&scene);
+    render.resolve();
+    println!(
+        "{} triangles rasterized, {:.1} ms",
+        stats.triangles_rasterized,
+        stats.total_time().as_secs_f32() * 1000.0
+    );
+
+    image::RgbImage::from_raw(WIDTH, HEIGHT, render.get_frame())
+        .unwrap()
+        .save(&output)
+        .unwrap();
+}
+
+//two seconds that loop: the turntable makes a full turn, the ball bounces and orbits back and forth,
+//the rig swings the camera
+fn animation(turntable: NodeId, orbit: NodeId, red_ball: NodeId, rig: NodeId) -> AnimationClip {
+    let turn = |angle: f32| Quat::from_axis_angle(Vec3::Y, angle);
+    let mut spin = Track::new(Interpolation::Linear);
+    for i in 0..=4 {
+        spin = spin.with_key(0.5 * i as f32, turn(PI / 6.0 + i as f32 * PI / 2.0));
+    }
+    let bounce = Track::new(Interpolation::CubicSpline)
+        .with_key(0.0, Vec3::new(1.0, 0.3, 0.0))
+        .with_key(0.5, Vec3::new(1.0, 0.9, 0.0))
+        .with_key(1.0, Vec3::new(1.0, 0.3, 0.0))
+        .with_key(1.5, Vec3::new(1.0, 0.9, 0.0))
+        .with_key(2.0, Vec3::new(1.0, 0.3, 0.0))
+        .with_smooth_tangents();
+    let squash = Track::new(Interpolation::Step)
+        .with_key(0.0, Vec3::new(0.6, 0.4, 0.6))
+        .with_key(0.1, Vec3::new(0.5, 0.5, 0.5))
+        .with_key(1.0, Vec3::new(0.6, 0.4, 0.6))
+        .with_key(1.1, Vec3::new(0.5, 0.5, 0.5));
+    let tilt = |angle: f32| Quat::from_axis_angle(Vec3::X, angle);
+    let swing = Track::new(Interpolation::Linear)
+        .with_key(0.0, tilt(-PI / 12.0))
+        .with_key(1.0, tilt(-PI / 5.0))
+        .with_key(2.0, tilt(-PI / 12.0));
+
+    AnimationClip::new("showcase")
+        .with_channel(AnimationChannel::new(AnimationTarget::Node(turntable)).with_rotation(spin))
+        .with_channel(
+            AnimationChannel::new(AnimationTarget::Node(orbit)).with_rotation(
+                Track::new(Interpolation::Linear)
+                    .with_key(0.0, turn(-PI / 3.0))
+                    .with_key(1.0, turn(PI / 3.0))
+                    .with_key(2.0, turn(-PI / 3.0)),
+            ),
+        )
+        .with_channel(
+            AnimationChannel::new(AnimationTarget::Node(red_ball))
+                .with_translation(bounce)
+                .with_scale(squash),
+        )
+        .with_channel(AnimationChannel::new(AnimationTarget::Node(rig)).with_rotation(swing))
+}
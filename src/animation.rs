@@ -0,0 +1,432 @@
+use crate::*;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    Step, //holds the previous key
+    #[default]
+    Linear, //slerp for rotations
+    CubicSpline, //hermite curve through the keys, uses their tangents
+}
+
+//values a track can animate
+pub trait Animatable: Copy + Default {
+    //a * wa + b * wb
+    fn combine(a: Self, wa: f32, b: Self, wb: f32) -> Self;
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        Self::combine(a, 1.0 - t, b, t)
+    }
+    //applied after the cubic spline
+    fn finish(self) -> Self {
+        self
+    }
+    //1 or -1, the sign that brings self next to other before they are combined
+    fn hemisphere(self, _other: Self) -> f32 {
+        1.0
+    }
+}
+impl Animatable for f32 {
+    fn combine(a: Self, wa: f32, b: Self, wb: f32) -> Self {
+        wa * a + wb * b
+    }
+}
+impl Animatable for Vec3 {
+    fn combine(a: Self, wa: f32, b: Self, wb: f32) -> Self {
+        wa * a + wb * b
+    }
+}
+impl Animatable for Quat {
+    fn combine(a: Self, wa: f32, b: Self, wb: f32) -> Self {
+        wa * a + wb * b
+    }
+    fn interpolate(a: Self, b: Self, t: f32) -> Self {
+        Quat::slerp(a, b, t)
+    }
+    fn finish(self) -> Self {
+        self.normalize()
+    }
+    //q and -q are the same rotation
+    fn hemisphere(self, other: Self) -> f32 {
+        if self.dot(&other) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Keyframe<T> {
+    pub time: f32, //seconds
+    pub value: T,
+    //derivatives per second, only used by the cubic spline
+    pub in_tangent: T,
+    pub out_tangent: T,
+}
+
+#[derive(Clone, Debug)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>, //sorted by time
+    pub interpolation: Interpolation,
+}
+impl<T: Animatable> Track<T> {
+    pub fn new(interpolation: Interpolation) -> Self {
+        Self {
+            keyframes: vec![],
+            interpolation,
+        }
+    }
+    //flat tangents
+    pub fn with_key(self, time: f32, value: T) -> Self {
+        self.with_cubic_key(time, value, T::default(), T::default())
+    }
+    pub fn with_cubic_key(mut self, time: f32, value: T, in_tangent: T, out_tangent: T) -> Self {
+        let keyframe = Keyframe {
+            time,
+            value,
+            in_tangent,
+            out_tangent,
+        };
+        let index = self.keyframes.partition_point(|key| key.time <= time);
+        self.keyframes.insert(index, keyframe);
+        self
+    }
+    //catmull-rom tangents for every key, the first and last key get one sided ones
+    pub fn with_smooth_tangents(mut self) -> Self {
+        let n = self.keyframes.len();
+        for i in 0..n {
+            let value = self.keyframes[i].value;
+            let prev = &self.keyframes[i.saturating_sub(1)];
+            let next = &self.keyframes[(i + 1).min(n - 1)];
+            let dt = next.time - prev.time;
+            if dt <= 0.0 {
+                continue;
+            }
+            let tangent = T::combine(
+                next.value,
+                next.value.hemisphere(value) / dt,
+                prev.value,
+                -prev.value.hemisphere(value) / dt,
+            );
+            self.keyframes[i].in_tangent = tangent;
+            self.keyframes[i].out_tangent = tangent;
+        }
+        self
+    }
+
+    pub fn get_keyframes(&self) -> &[Keyframe<T>] {
+        &self.keyframes
+    }
+    //time of the last key
+    pub fn get_duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |key| key.time)
+    }
+
+    //clamped to the first and last key, None without keys
+    pub fn sample(&self, time: f32) -> Option<T> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if time <= first.time {
+            return Some(first.value);
+        }
+        if time >= last.time {
+            return Some(last.value);
+        }
+        let index = self.keyframes.partition_point(|key| key.time <= time);
+        let (a, b) = (&self.keyframes[index - 1], &self.keyframes[index]);
+        let dt = b.time - a.time;
+        let t = (time - a.time) / dt;
+        Some(match self.interpolation {
+            Interpolation::Step => a.value,
+            Interpolation::Linear => T::interpolate(a.value, b.value, t),
+            Interpolation::CubicSpline => {
+                //b and its tangent flip together
+                let sign = b.value.hemisphere(a.value);
+                let (t2, t3) = (t * t, t * t * t);
+                let p = T::combine(
+                    a.value,
+                    2.0 * t3 - 3.0 * t2 + 1.0,
+                    b.value,
+                    (-2.0 * t3 + 3.0 * t2) * sign,
+                );
+                let m = T::combine(
+                    a.out_tangent,
+                    (t3 - 2.0 * t2 + t) * dt,
+                    b.in_tangent,
+                    (t3 - t2) * dt * sign,
+                );
+                T::combine(p, 1.0, m, 1.0).finish()
+            }
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AnimationTarget {
+    Node(NodeId),
+    Camera,             //the camera of the render, the scale track is ignored
+    Joint(usize),       //a joint of a skeleton, see `AnimationClip::apply_to_skeleton`
+    MorphTarget(usize), //the weight of a morph target, see `AnimationClip::apply_to_mesh`
+}
+
+//the tracks of one target, parts without a track keep their value
+#[derive(Clone, Debug)]
+pub struct AnimationChannel {
+    pub target: AnimationTarget,
+    pub translation: Option<Track<Vec3>>,
+    pub rotation: Option<Track<Quat>>,
+    pub scale: Option<Track<Vec3>>,
+    pub weight: Option<Track<f32>>, //of a morph target
+}
+impl AnimationChannel {
+    pub fn new(target: AnimationTarget) -> Self {
+        Self {
+            target,
+            translation: None,
+            rotation: None,
+            scale: None,
+            weight: None,
+        }
+    }
+    pub fn with_translation(mut self, track: Track<Vec3>) -> Self {
+        self.translation = Some(track);
+        self
+    }
+    pub fn with_rotation(mut self, track: Track<Quat>) -> Self {
+        self.rotation = Some(track);
+        self
+    }
+    pub fn with_scale(mut self, track: Track<Vec3>) -> Self {
+        self.scale = Some(track);
+        self
+    }
+    pub fn with_weight(mut self, track: Track<f32>) -> Self {
+        self.weight = Some(track);
+        self
+    }
+
+    pub fn get_duration(&self) -> f32 {
+        let duration = |track: Option<f32>| track.unwrap_or(0.0);
+        duration(self.translation.as_ref().map(Track::get_duration))
+            .max(duration(self.rotation.as_ref().map(Track::get_duration)))
+            .max(duration(self.scale.as_ref().map(Track::get_duration)))
+            .max(duration(self.weight.as_ref().map(Track::get_duration)))
+    }
+    pub fn sample(&self, time: f32, base: Transform) -> Transform {
+        let sample = |track: &Option<Track<Vec3>>, value: Vec3| {
+            track
+                .as_ref()
+                .and_then(|track| track.sample(time))
+                .unwrap_or(value)
+        };
+        Transform::new(
+            sample(&self.translation, base.translation),
+            self.rotation
+                .as_ref()
+                .and_then(|track| track.sample(time))
+                .unwrap_or(base.rotation),
+            sample(&self.scale, base.scale),
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AnimationClip {
+    pub name: String,
+    pub channels: Vec<AnimationChannel>,
+}
+impl AnimationClip {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            channels: vec![],
+        }
+    }
+    pub fn with_channel(mut self, channel: AnimationChannel) -> Self {
+        self.channels.push(channel);
+        self
+    }
+    //the end of the longest track
+    pub fn get_duration(&self) -> f32 {
+        self.channels
+            .iter()
+            .map(AnimationChannel::get_duration)
+            .fold(0.0, f32::max)
+    }
+
+    //poses the nodes of the scene
+    pub fn apply(&self, time: f32, scene: &mut Scene) {
+        for channel in self.channels.iter() {
+            if let AnimationTarget::Node(id) = channel.target {
+                let node = scene.get_node_mut(id);
+                node.transform = channel.sample(time, node.transform);
+            }
+        }
+    }
+    pub fn apply_to_skeleton(&self, time: f32, skeleton: &mut Skeleton) {
+        for channel in self.channels.iter() {
+            if let AnimationTarget::Joint(index) = channel.target {
+                let joint = &mut skeleton.joints[index];
+                joint.transform = channel.sample(time, joint.transform);
+            }
+        }
+    }
+    //the joints of the skeleton and the morph weights of the mesh
+    pub fn apply_to_mesh(&self, time: f32, mesh: &mut Mesh) {
+        if let Some(skeleton) = &mut mesh.skeleton {
+            self.apply_to_skeleton(time, skeleton);
+        }
+        for channel in self.channels.iter() {
+            if let (AnimationTarget::MorphTarget(index), Some(track)) =
+                (channel.target, &channel.weight)
+            {
+                if let Some(weight) = track.sample(time) {
+                    mesh.morph_weights[index] = weight;
+                }
+            }
+        }
+    }
+    pub fn apply_to_camera(&self, time: f32, camera: &mut Camera) {
+        for channel in self.channels.iter() {
+            if channel.target == AnimationTarget::Camera {
+                let base = Transform::new(
+                    camera.get_position(),
+                    camera.get_rotation(),
+                    Vec3::new(1.0, 1.0, 1.0),
+                );
+                let transform = channel.sample(time, base);
+                camera.set_position(transform.translation);
+                camera.set_rotation(transform.rotation);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlaybackMode {
+    #[default]
+    Once, //stops at the end
+    Loop,
+    PingPong, //forwards, then backwards
+}
+
+//turns the elapsed time into the time of the clip
+#[derive(Clone, Copy, Debug)]
+pub struct Playback {
+    pub duration: f32,
+    pub mode: PlaybackMode,
+    pub speed: f32,
+    pub playing: bool,
+    elapsed: f32, //scaled by the speed
+}
+impl Playback {
+    pub fn new(duration: f32) -> Self {
+        Self {
+            duration,
+            mode: PlaybackMode::Once,
+            speed: 1.0,
+            playing: true,
+            elapsed: 0.0,
+        }
+    }
+    pub fn with_mode(mut self, mode: PlaybackMode) -> Self {
+        self.mode = mode;
+        self
+    }
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn advance(&mut self, dt: f32) {
+        if self.playing {
+            self.elapsed += dt * self.speed;
+        }
+        if self.is_finished() {
+            self.playing = false;
+        }
+    }
+    pub fn seek(&mut self, elapsed: f32) {
+        self.elapsed = elapsed;
+    }
+    //a negative speed plays back to the start
+    pub fn is_finished(&self) -> bool {
+        self.mode == PlaybackMode::Once
+            && match self.speed < 0.0 {
+                true => self.elapsed <= 0.0,
+                false => self.elapsed >= self.duration,
+            }
+    }
+    pub fn get_time(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return 0.0;
+        }
+        match self.mode {
+            PlaybackMode::Once => self.elapsed.clamp(0.0, self.duration),
+            PlaybackMode::Loop => self.elapsed.rem_euclid(self.duration),
+            PlaybackMode::PingPong => {
+                let time = self.elapsed.rem_euclid(2.0 * self.duration);
+                if time > self.duration {
+                    2.0 * self.duration - time
+                } else {
+                    time
+                }
+            }
+        }
+    }
+}
+
+impl Render {
+    //draws `frames` frames at `fps` and saves them as png, see `save_frame` for the file names.
+    //the clip moves the camera of the render unless the scene has an active camera
+    pub fn render_sequence(
+        &mut self,
+        scene: &mut Scene,
+        clip: &AnimationClip,
+        playback: &mut Playback,
+        frames: usize,
+        fps: f32,
+        path: &str,
+    ) -> RenderStats {
+        let mut stats = RenderStats::default();
+        for frame in 0..frames {
+            let time = playback.get_time();
+            clip.apply(time, scene);
+            clip.apply_to_camera(time, &mut self.camera);
+            self.reset();
+            stats += self.draw_scene(scene);
+            self.resolve();
+            self.save_frame(path, frame);
+            playback.advance(1.0 / fps);
+        }
+        stats
+    }
+    //"{}" in the path becomes the frame number, without it the number goes before the extension
+    pub(crate) fn save_frame(&self, path: &str, frame: usize) {
+        let number = format!("{frame:04}");
+        let path = match path.contains("{}") {
+            true => path.replace("{}", &number),
+            false => {
+                let file = Path::new(path);
+                let name = format!(
+                    "{}_{number}",
+                    file.file_stem().unwrap_or_default().to_string_lossy()
+                );
+                let file = file.with_file_name(name);
+                match Path::new(path).extension() {
+                    Some(extension) => file.with_extension(extension),
+                    None => file,
+                }
+                .to_string_lossy()
+                .to_string()
+            }
+        };
+        if let Some(dir) = Path::new(&path).parent() {
+            std::fs::create_dir_all(dir).unwrap();
+        }
+        image::RgbImage::from_raw(self.width, self.height, self.get_frame())
+            .unwrap()
+            .save(&path)
+            .unwrap();
+    }
+}
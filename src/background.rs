@@ -0,0 +1,128 @@
+use crate::*;
+
+//what `Render::reset` fills the frame buffer with
+pub enum Background {
+    Color(Color),
+    Gradient { top: Color, bottom: Color }, //vertical, in screen space
+    Environment(EnvironmentMap),            //equirectangular panorama
+    Cubemap(Cubemap),
+}
+impl Default for Background {
+    fn default() -> Self {
+        Background::Color([0; 3])
+    }
+}
+impl From<Color> for Background {
+    fn from(color: Color) -> Self {
+        Background::Color(color)
+    }
+}
+impl From<EnvironmentMap> for Background {
+    fn from(environment: EnvironmentMap) -> Self {
+        Background::Environment(environment)
+    }
+}
+impl From<Cubemap> for Background {
+    fn from(cubemap: Cubemap) -> Self {
+        Background::Cubemap(cubemap)
+    }
+}
+
+//six faces in the order +x, -x, +y, -y, +z, -z, laid out like OpenGL cube maps
+pub struct Cubemap {
+    faces: Vec<Texture>,
+}
+impl Cubemap {
+    pub fn new(paths: [&str; 6]) -> Self {
+        Self {
+            faces: paths.into_iter().map(Texture::load_srgb).collect(),
+        }
+    }
+
+    pub fn sample(&self, direction: Vec3) -> Vec3 {
+        let (ax, ay, az) = (direction.x.abs(), direction.y.abs(), direction.z.abs());
+        //face, s and t(downwards) before dividing by the major axis
+        let (face, sc, tc, major) = if ax >= ay && ax >= az {
+            if direction.x > 0.0 {
+                (0, -direction.z, -direction.y, ax)
+            } else {
+                (1, direction.z, -direction.y, ax)
+            }
+        } else if ay >= az {
+            if direction.y > 0.0 {
+                (2, direction.x, direction.z, ay)
+            } else {
+                (3, direction.x, -direction.z, ay)
+            }
+        } else if direction.z > 0.0 {
+            (4, direction.x, -direction.y, az)
+        } else {
+            (5, -direction.x, -direction.y, az)
+        };
+
+        //keep away from 1.0, it would index one past the last texel
+        let s = (0.5 * (sc / major + 1.0)).clamp(0.0, 0.9999);
+        let t = (0.5 * (tc / major + 1.0)).clamp(0.0, 0.9999);
+        self.faces[face].get_pixel(Vec2::new(s, 1.0 - t))
+    }
+}
+
+fn lerp_color(a: Color, b: Color, t: f32) -> Color {
+    [0, 1, 2].map(|i| (a[i] as f32 + (b[i] as f32 - a[i] as f32) * t) as u8)
+}
+
+impl Render {
+    pub fn set_background(&mut self, background: impl Into<Background>) {
+        self.background = background.into()
+    }
+    pub fn get_background(&self) -> &Background {
+        &self.background
+    }
+
+    pub(crate) fn draw_background(&mut self) {
+        let (width, height) = (self.width, self.height);
+        match &self.background {
+            Background::Color(color) => match &mut self.hdr_buffer {
+                Some(hdr_buffer) => hdr_buffer.reset(decode_srgb(*color)),
+                None => self.frame_buffer.reset(*color),
+            },
+            Background::Gradient { top, bottom } => {
+                let (top, bottom) = (*top, *bottom);
+                for y in 0..height {
+                    let color = lerp_color(top, bottom, y as f32 / (height - 1).max(1) as f32);
+                    for x in 0..width {
+                        self.write_display_color(x, y, color);
+                    }
+                }
+            }
+            Background::Environment(_) | Background::Cubemap(_) => {
+                //pixel -> point on the far plane -> view ray
+                let Some(inv_view_projection) = self.get_view_projection().inv() else {
+                    //degenerated camera, no view rays
+                    match &mut self.hdr_buffer {
+                        Some(hdr_buffer) => hdr_buffer.reset(Vec3::zero()),
+                        None => self.frame_buffer.reset([0; 3]),
+                    }
+                    return;
+                };
+                let camera_position = self.camera.get_position();
+                for y in 0..height {
+                    for x in 0..width {
+                        let ndc_x = (x as f32 + 0.5) / width as f32 * 2.0 - 1.0;
+                        let ndc_y = 1.0 - (y as f32 + 0.5) / height as f32 * 2.0;
+                        let far = inv_view_projection
+                            .mul(Vec4::new(ndc_x, ndc_y, -1.0, 1.0))
+                            .perspective_divide();
+                        let direction = (far - camera_position).normalize();
+                        let radiance = match &self.background {
+                            Background::Environment(environment) => environment.sample(direction),
+                            Background::Cubemap(cubemap) => cubemap.sample(direction),
+                            _ => unreachable!(),
+                        };
+                        self.write_color(x, y, radiance);
+                    }
+                }
+            }
+        }
+    }
+}
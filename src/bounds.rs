@@ -0,0 +1,186 @@
+use crate::*;
+
+//axis aligned bounding box
+#[derive(Clone, Copy, Debug)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+    //None without points
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| {
+            Self::new(aabb.min.min(&point), aabb.max.max(&point))
+        }))
+    }
+    pub fn from_vertexes(vertexes: &[Vertex]) -> Option<Self> {
+        Self::from_points(vertexes.iter().map(|vertex| vertex.position))
+    }
+
+    pub fn get_center(&self) -> Vec3 {
+        0.5 * (self.min + self.max)
+    }
+    //half of the size
+    pub fn get_extent(&self) -> Vec3 {
+        0.5 * (self.max - self.min)
+    }
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self::new(self.min.min(&other.min), self.max.max(&other.max))
+    }
+    pub fn contains(&self, point: Vec3) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+    //the box around the transformed box, `matrix` has to be affine
+    pub fn transform(&self, matrix: &Matrix4) -> Self {
+        let center = matrix.mul(self.get_center().upgrade()).perspective_divide();
+        let e = self.get_extent();
+        let row = |i: usize| {
+            matrix.get(i, 0).abs() * e.x
+                + matrix.get(i, 1).abs() * e.y
+                + matrix.get(i, 2).abs() * e.z
+        };
+        let extent = Vec3::new(row(0), row(1), row(2));
+        Self::new(center - extent, center + extent)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+    //centred on the box of the points, not the smallest sphere but close for most meshes
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.get_center();
+        let radius2 = points
+            .into_iter()
+            .map(|point| (point - center).length2())
+            .fold(0.0, f32::max);
+        Some(Self::new(center, radius2.sqrt()))
+    }
+    pub fn from_aabb(aabb: &Aabb) -> Self {
+        Self::new(aabb.get_center(), aabb.get_extent().length2().sqrt())
+    }
+    //the radius grows with the biggest scale of the matrix
+    pub fn transform(&self, matrix: &Matrix4) -> Self {
+        let center = matrix.mul(self.center.upgrade()).perspective_divide();
+        let column = |j: usize| Vec3::new(matrix.get(0, j), matrix.get(1, j), matrix.get(2, j));
+        let scale2 = column(0)
+            .length2()
+            .max(column(1).length2())
+            .max(column(2).length2());
+        Self::new(center, self.radius * scale2.sqrt())
+    }
+}
+
+//normal·p + d, positive on the side the normal points to
+#[derive(Clone, Copy, Debug)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f32,
+}
+impl Plane {
+    pub fn distance(&self, point: Vec3) -> f32 {
+        self.normal.dot(&point) + self.d
+    }
+}
+
+//left, right, bottom, top, near, far. the normals point into the view volume
+#[derive(Clone, Copy, Debug)]
+pub struct FrustumPlanes {
+    pub planes: [Plane; 6],
+}
+impl FrustumPlanes {
+    //planes of the view volume of a (model) view projection matrix, in the space the matrix starts
+    //from: a view projection gives world planes, a model view projection the planes of the mesh
+    pub fn from_matrix(matrix: &Matrix4) -> Self {
+        //camera looks at -z so w is negative in front of the camera with both projections, the
+        //frustum is |x|,|y|,|z| <= -w which gives -w ± x >= 0 ...
+        let row = |i: usize| {
+            Vec4::new(
+                matrix.get(i, 0),
+                matrix.get(i, 1),
+                matrix.get(i, 2),
+                matrix.get(i, 3),
+            )
+        };
+        let w = row(3);
+        let plane = |axis: usize, side: f32| {
+            let r = row(axis);
+            let (a, b, c, d) = (
+                side * r.x - w.x,
+                side * r.y - w.y,
+                side * r.z - w.z,
+                side * r.w - w.w,
+            );
+            //a model scaled to 0 keeps only d, if the point it shrinks to is inside
+            let length = (a * a + b * b + c * c).sqrt();
+            let scale = if length > 0.0 { 1.0 / length } else { 1.0 };
+            Plane {
+                normal: scale * Vec3::new(a, b, c),
+                d: scale * d,
+            }
+        };
+        Self {
+            planes: [
+                plane(0, 1.0),
+                plane(0, -1.0),
+                plane(1, 1.0),
+                plane(1, -1.0),
+                plane(2, 1.0),
+                plane(2, -1.0),
+            ],
+        }
+    }
+
+    pub fn contains(&self, point: Vec3) -> bool {
+        self.planes.iter().all(|plane| plane.distance(point) >= 0.0)
+    }
+    //conservative, a box near a corner of the frustum can pass without being visible
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let (center, extent) = (aabb.get_center(), aabb.get_extent());
+        self.planes.iter().all(|plane| {
+            let n = plane.normal;
+            let radius = n.x.abs() * extent.x + n.y.abs() * extent.y + n.z.abs() * extent.z;
+            plane.distance(center) >= -radius
+        })
+    }
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.distance(sphere.center) >= -sphere.radius)
+    }
+}
+
+impl Camera {
+    //world space
+    pub fn get_frustum_planes(&mut self) -> FrustumPlanes {
+        FrustumPlanes::from_matrix(&(self.get_projection_matrix() * self.get_view_matrix()))
+    }
+}
+
+impl Mesh {
+    //of the posed vertexes, so skinned and morphed meshes are bounded in their current shape
+    pub fn get_aabb(&self) -> Aabb {
+        Aabb::from_vertexes(&self.get_posed_vertexes()).unwrap()
+    }
+    pub fn get_bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere::from_points(
+            self.get_posed_vertexes()
+                .iter()
+                .map(|vertex| vertex.position),
+        )
+        .unwrap()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::ShadedFragment;
+use crate::{ShadedFragment, Vec3};
 
 pub type Color = [u8; 3];
 
@@ -29,6 +29,12 @@ impl<T: Copy + Default> Buffer<T> {
     pub fn get_buffer(&self) -> &[T] {
         self.data.as_slice()
     }
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
     pub fn get_len(&self) -> usize {
         self.width as usize * self.height as usize
     }
@@ -50,6 +56,18 @@ impl FrameBuffer {
     pub fn draw_pixel(&mut self, pixel: (u32, u32), color: Color) {
         self.buffer.set_value(pixel.0, pixel.1, color)
     }
+    //ignore pixels out of screen instead of wrapping to the next row
+    pub fn draw_pixel_checked(&mut self, x: i32, y: i32, color: Color) {
+        if x >= 0 && y >= 0 && (x as u32) < self.get_width() && (y as u32) < self.get_height() {
+            self.buffer.set_value(x as u32, y as u32, color)
+        }
+    }
+    pub fn get_width(&self) -> u32 {
+        self.buffer.get_width()
+    }
+    pub fn get_height(&self) -> u32 {
+        self.buffer.get_height()
+    }
     pub fn flatten(&self) -> Vec<u8> {
         let mut flatten = Vec::with_capacity(self.buffer.get_len());
         self.buffer.get_buffer().iter().for_each(|color| {
@@ -65,6 +83,37 @@ impl FrameBuffer {
     }
 }
 
+//linear rgb, values above 1 are kept until tone mapping
+pub struct HdrFrameBuffer {
+    buffer: Buffer<Vec3>,
+}
+impl HdrFrameBuffer {
+    pub fn new(width: u32, height: u32, default: Vec3) -> Self {
+        Self {
+            buffer: Buffer::new_with_capacity(width, height, default),
+        }
+    }
+    pub fn set_color(&mut self, x: u32, y: u32, color: Vec3) {
+        self.buffer.set_value(x, y, color)
+    }
+    pub fn get_color(&self, x: u32, y: u32) -> Option<Vec3> {
+        if x < self.buffer.get_width() && y < self.buffer.get_height() {
+            self.buffer.get_value(x, y).copied()
+        } else {
+            None
+        }
+    }
+    pub fn get_width(&self) -> u32 {
+        self.buffer.get_width()
+    }
+    pub fn get_height(&self) -> u32 {
+        self.buffer.get_height()
+    }
+    pub fn reset(&mut self, default: Vec3) {
+        self.buffer.reset(default)
+    }
+}
+
 pub struct DepthBuffer {
     buffer: Buffer<f32>,
 }
@@ -91,6 +140,17 @@ impl DepthBuffer {
         )
     }
 
+    pub fn get_depth(&self, x: u32, y: u32) -> Option<f32> {
+        if x < self.buffer.get_width() && y < self.buffer.get_height() {
+            self.buffer.get_value(x, y).copied()
+        } else {
+            None
+        }
+    }
+    pub fn set_depth(&mut self, x: u32, y: u32, depth: f32) {
+        self.buffer.set_value(x, y, depth)
+    }
+
     pub fn reset(&mut self, default: f32) {
         self.buffer.reset(default)
     }
@@ -1,10 +1,20 @@
 use crate::*;
 
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Projection {
+    #[default]
+    Perspective, //by the fov of the frustum
+    Orthographic {
+        height: f32,
+    }, //of the view volume in world units, the width follows the aspect
+}
+
 #[derive(Clone)]
 pub struct Camera {
     frustum: Frustum,
     position: Vec3, //eye
     rotation: Quat,
+    projection: Projection,
 }
 impl Camera {
     pub fn new(frustum: Frustum, position: Vec3, target: Vec3) -> Self {
@@ -12,15 +22,41 @@ impl Camera {
             frustum,
             position,
             rotation: look_at(position, target, Vec3::Y),
+            projection: Projection::default(),
         }
     }
+    pub fn with_projection(mut self, projection: Projection) -> Self {
+        self.projection = projection;
+        self
+    }
 
     pub fn get_position(&self) -> Vec3 {
         self.position
     }
+    pub fn get_frustum(&self) -> &Frustum {
+        &self.frustum
+    }
+    pub fn get_frustum_mut(&mut self) -> &mut Frustum {
+        &mut self.frustum
+    }
+    pub fn get_projection(&self) -> Projection {
+        self.projection
+    }
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+    }
     pub fn get_dir(&self) -> Vec3 {
         -self.rotation.get_z_axis()
     }
+    pub fn get_rotation(&self) -> Quat {
+        self.rotation
+    }
+    pub fn set_position(&mut self, position: Vec3) {
+        self.position = position;
+    }
+    pub fn set_rotation(&mut self, rotation: Quat) {
+        self.rotation = rotation;
+    }
 
     pub fn rotation_around(&mut self, target: Vec3, rotation: Quat) {
         let diff = self.position - target;
@@ -28,13 +64,32 @@ impl Camera {
         self.position = Into::<Vec3>::into(m_rot.mul(diff.upgrade())) + target;
         self.rotation = rotation * self.rotation;
     }
+    //zoom, the fov changes by `offset` radians, an orthographic height by `offset` of itself
     pub fn scale(&mut self, offset: f32) {
-        let fov = self.frustum.fov + offset;
-        if fov < 180.0f32.to_radians() && fov > 0.0 {
-            self.frustum.fov = fov
+        match &mut self.projection {
+            Projection::Perspective => {
+                let fov = self.frustum.fov + offset;
+                if fov < 180.0f32.to_radians() && fov > 0.0 {
+                    self.frustum.fov = fov
+                }
+            }
+            Projection::Orthographic { height } => {
+                if offset > -1.0 {
+                    *height *= 1.0 + offset;
+                }
+            }
         }
     }
 
+    //half width and height of the view at `distance` in front of the camera, in world units
+    pub fn get_view_extent(&self, distance: f32) -> Vec2 {
+        let height = match self.projection {
+            Projection::Perspective => distance * (self.frustum.fov * 0.5).tan(),
+            Projection::Orthographic { height } => 0.5 * height,
+        };
+        Vec2::new(height * self.frustum.aspect, height)
+    }
+
     pub fn get_view_matrix(&mut self) -> Matrix4 {
         let mat_rot_inv = self.rotation.inv().get_rotation();
 
@@ -47,7 +102,10 @@ impl Camera {
         mat_rot_inv * mat_trans_inv
     }
     pub fn get_projection_matrix(&self) -> Matrix4 {
-        self.frustum.perspective_projection()
+        match self.projection {
+            Projection::Perspective => self.frustum.perspective_projection(),
+            Projection::Orthographic { height } => self.frustum.orth_projection(height),
+        }
     }
 }
 
@@ -67,6 +125,8 @@ pub struct Frustum {
     far: f32,  //far plane: z= -far   in view ---> plane z = -1.0 in ndc
     fov: f32,
     aspect: f32, //width/height
+    //part of the image plane that is rendered, in ndc of the whole frustum. None is all of it
+    window: Option<(Vec2, Vec2)>,
 }
 impl Frustum {
     pub fn new(near: f32, far: f32, fov: f32, aspect: f32) -> Self {
@@ -75,10 +135,52 @@ impl Frustum {
             far,
             fov,
             aspect,
+            window: None,
         }
     }
-    pub fn orth_projection(&self) -> Matrix4 {
-        let h = 2.0 * self.near * (self.fov * 0.5).tan();
+    //off-centre frustum from `min` to `max` in [-1,1]. for a tile of a bigger image, set the aspect
+    //to the one of the whole image
+    pub fn with_window(mut self, min: Vec2, max: Vec2) -> Self {
+        self.window = Some((min, max));
+        self
+    }
+    //oblique frustum, the image plane is shifted by `shift` half widths/heights. stereo pairs use
+    //opposite horizontal shifts so the two views converge at a distance
+    pub fn with_lens_shift(self, shift: Vec2) -> Self {
+        let (min, max) = self
+            .window
+            .unwrap_or((Vec2::new(-1.0, -1.0), Vec2::new(1.0, 1.0)));
+        self.with_window(min + shift, max + shift)
+    }
+    //the window of tile (x, y) when the image is split in `columns` x `rows`, y goes down like the pixels
+    pub fn with_tile(self, x: u32, y: u32, columns: u32, rows: u32) -> Self {
+        let step = Vec2::new(2.0 / columns as f32, 2.0 / rows as f32);
+        let min = Vec2::new(-1.0 + x as f32 * step.x, 1.0 - (y + 1) as f32 * step.y);
+        self.with_window(min, min + step)
+    }
+    pub fn get_fov(&self) -> f32 {
+        self.fov
+    }
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov;
+    }
+    pub fn get_aspect(&self) -> f32 {
+        self.aspect
+    }
+    pub fn get_near(&self) -> f32 {
+        self.near
+    }
+    pub fn get_far(&self) -> f32 {
+        self.far
+    }
+    //the view volume is `height` high, the fov is not used
+    pub fn orth_projection(&self, height: f32) -> Matrix4 {
+        //w is -1 in front of the camera like with the perspective projection. the whole clip
+        //position is negated so x/w, y/w and z/w stay the same
+        let negate_clip = -1.0 * Matrix4::ident();
+        negate_clip * self.window_projection() * self.orth_box(height)
+    }
+    fn orth_box(&self, h: f32) -> Matrix4 {
         let w = h * self.aspect;
 
         let near_z = -self.near;
@@ -113,6 +215,22 @@ impl Frustum {
             0.0,    0.0,    1.0,    0.0,]
         );
 
-        self.orth_projection() * per_to_orth
+        //the perspective frustum squashed into the box of its near plane
+        let h = 2.0 * self.near * (self.fov * 0.5).tan();
+        self.window_projection() * self.orth_box(h) * per_to_orth
+    }
+    //maps the window to [-1,1], applied to clip coordinates so w takes care of the perspective
+    #[rustfmt::skip]
+    fn window_projection(&self) -> Matrix4 {
+        let Some((min, max)) = self.window else {
+            return Matrix4::ident();
+        };
+        let size = max - min;
+        Matrix4::new([
+            2.0 / size.x,   0.0,    0.0,    -(max.x + min.x) / size.x,
+            0.0,    2.0 / size.y,   0.0,    -(max.y + min.y) / size.y,
+            0.0,    0.0,    1.0,    0.0,
+            0.0,    0.0,    0.0,    1.0,
+        ])
     }
 }
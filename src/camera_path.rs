@@ -0,0 +1,206 @@
+use crate::*;
+use std::fmt;
+use std::fs::read_to_string;
+
+#[derive(Clone, Copy, Debug)]
+pub struct CameraKey {
+    pub time: f32, //seconds
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub fov: f32,
+    pub projection: Projection,
+}
+impl CameraKey {
+    pub fn from_camera(time: f32, camera: &Camera) -> Self {
+        Self {
+            time,
+            position: camera.get_position(),
+            rotation: camera.get_rotation(),
+            fov: camera.get_frustum().get_fov(),
+            projection: camera.get_projection(),
+        }
+    }
+    pub fn apply(&self, camera: &mut Camera) {
+        camera.set_position(self.position);
+        camera.set_rotation(self.rotation);
+        camera.get_frustum_mut().set_fov(self.fov);
+        camera.set_projection(self.projection);
+    }
+}
+
+//recorded camera states, played back through a catmull-rom spline for the position, slerp for the
+//rotation and linear for the fov and the orthographic height
+#[derive(Clone, Debug, Default)]
+pub struct CameraPath {
+    keys: Vec<CameraKey>, //sorted by time
+}
+impl CameraPath {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    //`steps` keys on a full turn around the y axis through `target`, keeps the height and distance
+    //of the camera. the spline cuts the corners between the keys, more steps stay closer to the circle
+    pub fn orbit(camera: &Camera, target: Vec3, duration: f32, steps: usize) -> Self {
+        let mut path = Self::new();
+        let mut camera = camera.clone();
+        let angle = 2.0 * std::f32::consts::PI / steps as f32;
+        for step in 0..=steps {
+            path.record(duration * step as f32 / steps as f32, &camera);
+            camera.rotation_around(target, Quat::from_axis_angle(Vec3::Y, angle));
+        }
+        path
+    }
+
+    pub fn record(&mut self, time: f32, camera: &Camera) {
+        self.add_key(CameraKey::from_camera(time, camera));
+    }
+    pub fn add_key(&mut self, key: CameraKey) {
+        let index = self.keys.partition_point(|other| other.time <= key.time);
+        self.keys.insert(index, key);
+    }
+    pub fn clear(&mut self) {
+        self.keys.clear();
+    }
+    pub fn get_keys(&self) -> &[CameraKey] {
+        &self.keys
+    }
+    pub fn get_duration(&self) -> f32 {
+        self.keys.last().map_or(0.0, |key| key.time)
+    }
+
+    //clamped to the first and last key, None without keys
+    pub fn sample(&self, time: f32) -> Option<CameraKey> {
+        let first = self.keys.first()?;
+        let last = self.keys.last()?;
+        if time <= first.time {
+            return Some(CameraKey { time, ..*first });
+        }
+        if time >= last.time {
+            return Some(CameraKey { time, ..*last });
+        }
+        let index = self.keys.partition_point(|key| key.time <= time);
+        let (a, b) = (&self.keys[index - 1], &self.keys[index]);
+        let t = (time - a.time) / (b.time - a.time);
+
+        //the tangents only depend on the neighbours, a spline through them matches the whole path
+        let neighbours = &self.keys[index.saturating_sub(2)..(index + 2).min(self.keys.len())];
+        let position = neighbours
+            .iter()
+            .fold(Track::new(Interpolation::CubicSpline), |track, key| {
+                track.with_key(key.time, key.position)
+            })
+            .with_smooth_tangents()
+            .sample(time)
+            .unwrap();
+        Some(CameraKey {
+            time,
+            position,
+            rotation: Quat::slerp(a.rotation, b.rotation, t),
+            fov: a.fov + (b.fov - a.fov) * t,
+            //a change of the projection happens at the next key
+            projection: match (a.projection, b.projection) {
+                (
+                    Projection::Orthographic { height: a },
+                    Projection::Orthographic { height: b },
+                ) => Projection::Orthographic {
+                    height: a + (b - a) * t,
+                },
+                (projection, _) => projection,
+            },
+        })
+    }
+    pub fn apply(&self, time: f32, camera: &mut Camera) {
+        if let Some(key) = self.sample(time) {
+            key.apply(camera);
+        }
+    }
+
+    pub fn load(path: &str) -> Self {
+        Self::parse(&read_to_string(path).unwrap())
+            .unwrap_or_else(|error| panic!("{path}: {error}"))
+    }
+    //one key per line: time, position, rotation with the scalar part first, fov in radians and
+    //the height of the view volume for an orthographic key
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut path = Self::new();
+        for (number, line) in source.lines().map(str::trim).enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values = line
+                .split_whitespace()
+                .map(|value| value.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|error| format!("line {}: {error} in camera key {line}", number + 1))?;
+            let projection = match values.len() {
+                9 => Projection::Perspective,
+                10 => Projection::Orthographic { height: values[9] },
+                count => {
+                    return Err(format!(
+                        "line {}: {count} values in camera key {line}, expected 9 or 10",
+                        number + 1
+                    ))
+                }
+            };
+            path.add_key(CameraKey {
+                time: values[0],
+                position: Vec3::new(values[1], values[2], values[3]),
+                rotation: Quat::new(values[4], values[5], values[6], values[7]),
+                fov: values[8],
+                projection,
+            });
+        }
+        Ok(path)
+    }
+    pub fn save(&self, path: &str) {
+        std::fs::write(path, self.to_string()).unwrap();
+    }
+}
+impl fmt::Display for CameraPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "# time position rotation(scalar first) fov [orthographic height]"
+        )?;
+        for key in self.keys.iter() {
+            let (p, r) = (key.position, key.rotation);
+            write!(
+                f,
+                "{} {} {} {} {} {} {} {} {}",
+                key.time, p.x, p.y, p.z, r.x, r.y, r.z, r.w, key.fov
+            )?;
+            match key.projection {
+                Projection::Perspective => writeln!(f)?,
+                Projection::Orthographic { height } => writeln!(f, " {height}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Render {
+    //like `render_sequence`, the path moves the camera of the render and the active camera of the
+    //scene is ignored
+    pub fn render_camera_path(
+        &mut self,
+        scene: &mut Scene,
+        camera_path: &CameraPath,
+        playback: &mut Playback,
+        frames: usize,
+        fps: f32,
+        path: &str,
+    ) -> RenderStats {
+        let active_camera = scene.active_camera.take();
+        let mut stats = RenderStats::default();
+        for frame in 0..frames {
+            camera_path.apply(playback.get_time(), &mut self.camera);
+            self.reset();
+            stats += self.draw_scene(scene);
+            self.resolve();
+            self.save_frame(path, frame);
+            playback.advance(1.0 / fps);
+        }
+        scene.active_camera = active_camera;
+        stats
+    }
+}
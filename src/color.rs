@@ -0,0 +1,175 @@
+use crate::*;
+use std::sync::OnceLock;
+
+const BLUE_NOISE_SIZE: usize = 64;
+const BLUE_NOISE_SIGMA: f32 = 1.9;
+const BLUE_NOISE_RADIUS: i32 = 8;
+
+#[rustfmt::skip]
+const BAYER_4X4: [f32; 16] = [
+     0.0,  8.0,  2.0, 10.0,
+    12.0,  4.0, 14.0,  6.0,
+     3.0, 11.0,  1.0,  9.0,
+    15.0,  7.0, 13.0,  5.0,
+];
+
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+pub fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+//8 bit srgb colour -> linear rgb, through a table since every texel of a colour map goes through it
+pub fn decode_srgb(color: Color) -> Vec3 {
+    static TABLE: OnceLock<[f32; 256]> = OnceLock::new();
+    let table = TABLE.get_or_init(|| {
+        let mut table = [0.0; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = srgb_to_linear(i as f32 / 255.0);
+        }
+        table
+    });
+    Vec3::new(
+        table[color[0] as usize],
+        table[color[1] as usize],
+        table[color[2] as usize],
+    )
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ToneMapping {
+    Clamp,    //no tone mapping, everything above 1 is clipped
+    Reinhard, //c / (1 + c)
+    Aces,     //filmic curve fitted by Krzysztof Narkowicz
+}
+impl ToneMapping {
+    pub fn apply(&self, color: Vec3) -> Vec3 {
+        let map = |c: f32| match self {
+            ToneMapping::Clamp => c,
+            ToneMapping::Reinhard => c / (1.0 + c),
+            ToneMapping::Aces => (c * (2.51 * c + 0.03)) / (c * (2.43 * c + 0.59) + 0.14),
+        };
+        Vec3::new(
+            map(color.x.max(0.0)).clamp(0.0, 1.0),
+            map(color.y.max(0.0)).clamp(0.0, 1.0),
+            map(color.z.max(0.0)).clamp(0.0, 1.0),
+        )
+    }
+}
+
+//noise added before rounding to 8 bits, breaks up the banding of smooth gradients
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dither {
+    None,
+    Ordered,   //4x4 bayer matrix
+    BlueNoise, //64x64 void-and-cluster pattern
+}
+impl Dither {
+    //threshold in (0,1) for a pixel
+    pub fn threshold(&self, x: u32, y: u32) -> f32 {
+        match self {
+            Dither::None => 0.5,
+            Dither::Ordered => (BAYER_4X4[(y % 4 * 4 + x % 4) as usize] + 0.5) / 16.0,
+            Dither::BlueNoise => {
+                let size = BLUE_NOISE_SIZE as u32;
+                blue_noise()[(y % size * size + x % size) as usize]
+            }
+        }
+    }
+}
+
+//ranks pixels by repeatedly filling the biggest void of the already ranked ones,
+//only the second phase of Ulichney's void-and-cluster but close enough for dithering
+fn blue_noise() -> &'static [f32] {
+    static BLUE_NOISE: OnceLock<Vec<f32>> = OnceLock::new();
+    BLUE_NOISE.get_or_init(|| {
+        let size = BLUE_NOISE_SIZE as i32;
+        let n = BLUE_NOISE_SIZE * BLUE_NOISE_SIZE;
+        let mut energy = vec![0.0f32; n];
+        let mut rank = vec![f32::NAN; n];
+        for r in 0..n {
+            let mut index = 0;
+            for i in 0..n {
+                if rank[i].is_nan() && (!rank[index].is_nan() || energy[i] < energy[index]) {
+                    index = i;
+                }
+            }
+            rank[index] = (r as f32 + 0.5) / n as f32;
+
+            let (x, y) = (index as i32 % size, index as i32 / size);
+            for dy in -BLUE_NOISE_RADIUS..=BLUE_NOISE_RADIUS {
+                for dx in -BLUE_NOISE_RADIUS..=BLUE_NOISE_RADIUS {
+                    let distance2 = (dx * dx + dy * dy) as f32;
+                    let wrapped = (y + dy).rem_euclid(size) * size + (x + dx).rem_euclid(size);
+                    energy[wrapped as usize] +=
+                        (-distance2 / (2.0 * BLUE_NOISE_SIGMA * BLUE_NOISE_SIGMA)).exp();
+                }
+            }
+        }
+        rank
+    })
+}
+
+//linear radiance -> 8 bit display colour
+#[derive(Clone, Copy, Debug)]
+pub struct OutputTransform {
+    pub exposure: f32, //multiplier before tone mapping
+    pub tone_mapping: ToneMapping,
+    pub srgb: bool, //encode with the srgb curve, false writes linear values
+    pub dither: Dither,
+}
+impl OutputTransform {
+    pub fn with_exposure(mut self, exposure: f32) -> Self {
+        self.exposure = exposure;
+        self
+    }
+    pub fn with_tone_mapping(mut self, tone_mapping: ToneMapping) -> Self {
+        self.tone_mapping = tone_mapping;
+        self
+    }
+    pub fn with_srgb(mut self, srgb: bool) -> Self {
+        self.srgb = srgb;
+        self
+    }
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    pub fn encode(&self, color: Vec3, x: u32, y: u32) -> Color {
+        let mapped = self.tone_mapping.apply(self.exposure * color);
+        let threshold = self.dither.threshold(x, y);
+        let quantize = |c: f32| {
+            let c = if self.srgb { linear_to_srgb(c) } else { c };
+            (c * 255.0 + threshold).floor().clamp(0.0, 255.0) as u8
+        };
+        [quantize(mapped.x), quantize(mapped.y), quantize(mapped.z)]
+    }
+    //back to linear with the same curve, the tone mapping isn't undone
+    pub fn decode(&self, color: Color) -> Vec3 {
+        if self.srgb {
+            decode_srgb(color)
+        } else {
+            (1.0 / 255.0) * Vec3::new(color[0] as f32, color[1] as f32, color[2] as f32)
+        }
+    }
+}
+impl Default for OutputTransform {
+    fn default() -> Self {
+        Self {
+            exposure: 1.0,
+            tone_mapping: ToneMapping::Clamp,
+            srgb: true,
+            dither: Dither::None,
+        }
+    }
+}
@@ -0,0 +1,447 @@
+use crate::*;
+use std::f32::consts::FRAC_PI_2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Middle,
+    Right,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Movement {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+//input of the controllers, translated from the events of the window. the cursor is in [-1,1] with
+//y up like ndc, so the controllers don't depend on the size of the window
+#[derive(Clone, Copy, Debug)]
+pub enum InputEvent {
+    Press(MouseButton, Vec2),
+    Release(MouseButton),
+    Move(Vec2),
+    Scroll(f32), //wheel steps, positive towards the scene
+    KeyDown(Movement),
+    KeyUp(Movement),
+}
+
+//pixel position -> cursor of the input events
+pub fn cursor(x: f32, y: f32, width: f32, height: f32) -> Vec2 {
+    Vec2::new(2.0 * x / width - 1.0, 1.0 - 2.0 * y / height)
+}
+
+pub trait CameraController {
+    fn handle(&mut self, event: InputEvent);
+    //moves the camera towards the goal of the input, false once it has settled
+    fn update(&mut self, camera: &mut Camera, dt: f32) -> bool;
+}
+
+//part of the remaining way covered in `dt`, `smoothing` is the time constant in seconds
+fn damp(smoothing: f32, dt: f32) -> f32 {
+    if smoothing <= 0.0 {
+        1.0
+    } else {
+        1.0 - (-dt / smoothing).exp()
+    }
+}
+
+//camera at `distance` from the target, looking at it
+#[derive(Clone, Copy, Debug)]
+struct Orbit {
+    target: Vec3,
+    distance: f32,
+    rotation: Quat,
+}
+impl Orbit {
+    fn new(camera: &Camera, target: Vec3) -> Self {
+        let position = camera.get_position();
+        Self {
+            target,
+            distance: (position - target).length2().sqrt(),
+            rotation: look_at(position, target, Vec3::Y),
+        }
+    }
+    fn apply(&self, camera: &mut Camera) {
+        camera.set_position(self.target + self.distance * self.rotation.rotate(Vec3::Z));
+        camera.set_rotation(self.rotation);
+    }
+    //moves the target in the view plane, `offset` is in world units
+    fn pan(&mut self, offset: Vec2) {
+        let offset = self.rotation.rotate(Vec3::new(offset.x, offset.y, 0.0));
+        self.target = self.target - offset;
+    }
+    //moves the eye, `steps` > 0 gets closer
+    fn dolly(&mut self, steps: f32, speed: f32, min_distance: f32) {
+        self.distance = (self.distance * speed.powf(-steps)).max(min_distance);
+    }
+    fn settled(&self, goal: &Orbit) -> bool {
+        let eps = 1e-4 * goal.distance;
+        (self.target - goal.target).length2() < eps * eps
+            && (self.distance - goal.distance).abs() < eps
+            && self.rotation.dot(&goal.rotation).abs() > 1.0 - 1e-7
+    }
+}
+
+//what the dragged button does
+#[derive(Clone, Copy, Debug)]
+struct Drag {
+    button: MouseButton,
+    last: Vec2,
+}
+
+//left drag rolls a virtual trackball around the target, right or middle drag pans, the wheel dollies
+#[derive(Clone, Debug)]
+pub struct ArcballController {
+    goal: Orbit,
+    current: Orbit,
+    drag: Option<Drag>,
+    extent: Vec2, //half size of the view at the target, keeps the target under the cursor when panning
+    pub smoothing: f32,
+    pub dolly_speed: f32, //distance factor per wheel step
+    pub min_distance: f32,
+}
+impl ArcballController {
+    pub fn new(camera: &Camera, target: Vec3) -> Self {
+        let orbit = Orbit::new(camera, target);
+        Self {
+            goal: orbit,
+            current: orbit,
+            drag: None,
+            extent: camera.get_view_extent(orbit.distance),
+            smoothing: 0.08,
+            dolly_speed: 1.1,
+            min_distance: 0.01,
+        }
+    }
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+    pub fn get_target(&self) -> Vec3 {
+        self.goal.target
+    }
+
+    //the cursor on a sphere that turns into a hyperbola outside of it, so the edge of the window
+    //still rolls
+    fn project(&self, cursor: Vec2) -> Vec3 {
+        let aspect = self.extent.x / self.extent.y;
+        let (x, y) = (cursor.x * aspect, cursor.y);
+        let r2 = x * x + y * y;
+        let z = if r2 <= 0.5 {
+            (1.0 - r2).sqrt()
+        } else {
+            0.5 / r2.sqrt()
+        };
+        Vec3::new(x, y, z).normalize()
+    }
+}
+impl CameraController for ArcballController {
+    fn handle(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Press(button, position) => {
+                self.drag = Some(Drag {
+                    button,
+                    last: position,
+                })
+            }
+            InputEvent::Release(_) => self.drag = None,
+            InputEvent::Move(position) => {
+                let Some(drag) = self.drag.as_mut() else {
+                    return;
+                };
+                let (last, button) = (drag.last, drag.button);
+                drag.last = position;
+                match button {
+                    //the ball turns with the cursor, so the camera turns the other way
+                    MouseButton::Left => {
+                        let arc =
+                            Quat::from_rotation_arc(self.project(last), self.project(position));
+                        self.goal.rotation = (self.goal.rotation * arc.conjugate()).normalize();
+                    }
+                    MouseButton::Middle | MouseButton::Right => {
+                        let delta = position - last;
+                        let extent = self.extent;
+                        self.goal
+                            .pan(Vec2::new(delta.x * extent.x, delta.y * extent.y));
+                    }
+                }
+            }
+            InputEvent::Scroll(steps) => {
+                self.goal.dolly(steps, self.dolly_speed, self.min_distance)
+            }
+            InputEvent::KeyDown(_) | InputEvent::KeyUp(_) => {}
+        }
+    }
+    fn update(&mut self, camera: &mut Camera, dt: f32) -> bool {
+        let moving = !self.current.settled(&self.goal);
+        if moving {
+            let t = damp(self.smoothing, dt);
+            self.current = Orbit {
+                target: Vec3::interpolate(self.current.target, self.goal.target, t),
+                distance: self.current.distance + (self.goal.distance - self.current.distance) * t,
+                rotation: Quat::slerp(self.current.rotation, self.goal.rotation, t),
+            };
+            if self.current.settled(&self.goal) {
+                self.current = self.goal;
+            }
+        }
+        self.current.apply(camera);
+        self.extent = camera.get_view_extent(self.goal.distance);
+        moving
+    }
+}
+
+//orbit around the target by yaw around the world up and a clamped pitch, the horizon stays level.
+//right or middle drag pans, the wheel dollies
+#[derive(Clone, Debug)]
+pub struct TurntableController {
+    goal: Turntable,
+    current: Turntable,
+    drag: Option<Drag>,
+    extent: Vec2,
+    pub smoothing: f32,
+    pub rotate_speed: f32, //radians per half window
+    pub pitch_limits: (f32, f32),
+    pub dolly_speed: f32,
+    pub min_distance: f32,
+}
+#[derive(Clone, Copy, Debug)]
+struct Turntable {
+    yaw: f32,
+    pitch: f32, //negative looks down
+    target: Vec3,
+    distance: f32,
+}
+impl Turntable {
+    fn orbit(&self) -> Orbit {
+        Orbit {
+            target: self.target,
+            distance: self.distance,
+            rotation: Quat::from_axis_angle(Vec3::Y, self.yaw)
+                * Quat::from_axis_angle(Vec3::X, self.pitch),
+        }
+    }
+}
+impl TurntableController {
+    pub fn new(camera: &Camera, target: Vec3) -> Self {
+        let offset = camera.get_position() - target;
+        let distance = offset.length2().sqrt();
+        let turntable = Turntable {
+            yaw: offset.x.atan2(offset.z),
+            pitch: -(offset.y / distance).clamp(-1.0, 1.0).asin(),
+            target,
+            distance,
+        };
+        let limit = FRAC_PI_2 - 0.01;
+        Self {
+            goal: turntable,
+            current: turntable,
+            drag: None,
+            extent: camera.get_view_extent(distance),
+            smoothing: 0.08,
+            rotate_speed: 2.0,
+            pitch_limits: (-limit, limit),
+            dolly_speed: 1.1,
+            min_distance: 0.01,
+        }
+    }
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+    pub fn with_pitch_limits(mut self, min: f32, max: f32) -> Self {
+        self.pitch_limits = (min, max);
+        self.goal.pitch = self.goal.pitch.clamp(min, max);
+        self
+    }
+    pub fn get_target(&self) -> Vec3 {
+        self.goal.target
+    }
+}
+impl CameraController for TurntableController {
+    fn handle(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Press(button, position) => {
+                self.drag = Some(Drag {
+                    button,
+                    last: position,
+                })
+            }
+            InputEvent::Release(_) => self.drag = None,
+            InputEvent::Move(position) => {
+                let Some(drag) = self.drag.as_mut() else {
+                    return;
+                };
+                let delta = position - drag.last;
+                drag.last = position;
+                match drag.button {
+                    MouseButton::Left => {
+                        let (min, max) = self.pitch_limits;
+                        self.goal.yaw -= delta.x * self.rotate_speed;
+                        self.goal.pitch =
+                            (self.goal.pitch + delta.y * self.rotate_speed).clamp(min, max);
+                    }
+                    MouseButton::Middle | MouseButton::Right => {
+                        let mut orbit = self.goal.orbit();
+                        orbit.pan(Vec2::new(delta.x * self.extent.x, delta.y * self.extent.y));
+                        self.goal.target = orbit.target;
+                    }
+                }
+            }
+            InputEvent::Scroll(steps) => {
+                let mut orbit = self.goal.orbit();
+                orbit.dolly(steps, self.dolly_speed, self.min_distance);
+                self.goal.distance = orbit.distance;
+            }
+            InputEvent::KeyDown(_) | InputEvent::KeyUp(_) => {}
+        }
+    }
+    fn update(&mut self, camera: &mut Camera, dt: f32) -> bool {
+        let moving = !self.current.orbit().settled(&self.goal.orbit());
+        if moving {
+            let t = damp(self.smoothing, dt);
+            let lerp = |a: f32, b: f32| a + (b - a) * t;
+            self.current = Turntable {
+                yaw: lerp(self.current.yaw, self.goal.yaw),
+                pitch: lerp(self.current.pitch, self.goal.pitch),
+                target: Vec3::interpolate(self.current.target, self.goal.target, t),
+                distance: lerp(self.current.distance, self.goal.distance),
+            };
+            if self.current.orbit().settled(&self.goal.orbit()) {
+                self.current = self.goal;
+            }
+        }
+        self.current.orbit().apply(camera);
+        self.extent = camera.get_view_extent(self.goal.distance);
+        moving
+    }
+}
+
+//first person camera: dragging looks around, the movement keys fly in the direction of the view,
+//up and down follow the world. the wheel dollies along the view
+#[derive(Clone, Debug)]
+pub struct FlyController {
+    goal: Fly,
+    current: Fly,
+    drag: Option<Drag>,
+    held: [bool; 6], //by Movement
+    pub smoothing: f32,
+    pub speed: f32,      //world units per second
+    pub look_speed: f32, //radians per half window
+    pub dolly_step: f32, //world units per wheel step
+}
+#[derive(Clone, Copy, Debug)]
+struct Fly {
+    position: Vec3,
+    yaw: f32,
+    pitch: f32,
+}
+impl Fly {
+    fn rotation(&self) -> Quat {
+        Quat::from_axis_angle(Vec3::Y, self.yaw) * Quat::from_axis_angle(Vec3::X, self.pitch)
+    }
+}
+impl FlyController {
+    pub fn new(camera: &Camera) -> Self {
+        let dir = camera.get_dir();
+        let fly = Fly {
+            position: camera.get_position(),
+            yaw: (-dir.x).atan2(-dir.z),
+            pitch: dir.y.clamp(-1.0, 1.0).asin(),
+        };
+        Self {
+            goal: fly,
+            current: fly,
+            drag: None,
+            held: [false; 6],
+            smoothing: 0.05,
+            speed: 1.0,
+            look_speed: 1.5,
+            dolly_step: 0.1,
+        }
+    }
+    pub fn with_smoothing(mut self, smoothing: f32) -> Self {
+        self.smoothing = smoothing;
+        self
+    }
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    //the movement of the held keys in camera space, forward is -z
+    fn velocity(&self) -> Vec3 {
+        let axis = |positive: Movement, negative: Movement| {
+            self.held[positive as usize] as i32 as f32 - self.held[negative as usize] as i32 as f32
+        };
+        Vec3::new(
+            axis(Movement::Right, Movement::Left),
+            axis(Movement::Up, Movement::Down),
+            axis(Movement::Backward, Movement::Forward),
+        )
+    }
+}
+impl CameraController for FlyController {
+    fn handle(&mut self, event: InputEvent) {
+        match event {
+            InputEvent::Press(button, position) => {
+                self.drag = Some(Drag {
+                    button,
+                    last: position,
+                })
+            }
+            InputEvent::Release(_) => self.drag = None,
+            InputEvent::Move(position) => {
+                let Some(drag) = self.drag.as_mut() else {
+                    return;
+                };
+                let delta = position - drag.last;
+                drag.last = position;
+                let limit = FRAC_PI_2 - 0.01;
+                self.goal.yaw -= delta.x * self.look_speed;
+                self.goal.pitch =
+                    (self.goal.pitch + delta.y * self.look_speed).clamp(-limit, limit);
+            }
+            InputEvent::Scroll(steps) => {
+                let forward = self.goal.rotation().rotate(-Vec3::Z);
+                self.goal.position += (steps * self.dolly_step) * forward;
+            }
+            InputEvent::KeyDown(movement) => self.held[movement as usize] = true,
+            InputEvent::KeyUp(movement) => self.held[movement as usize] = false,
+        }
+    }
+    fn update(&mut self, camera: &mut Camera, dt: f32) -> bool {
+        let velocity = self.velocity();
+        if velocity.length2() > 0.0 {
+            //horizontal movement turns with the view, vertical movement stays on the world up
+            let rotation = self.goal.rotation();
+            let horizontal = rotation.rotate(Vec3::new(velocity.x, 0.0, velocity.z));
+            let step = horizontal + Vec3::new(0.0, velocity.y, 0.0);
+            self.goal.position += (self.speed * dt) * step.normalize();
+        }
+
+        let eps = 1e-5;
+        let moving = (self.current.position - self.goal.position).length2() > eps * eps
+            || (self.current.yaw - self.goal.yaw).abs() > eps
+            || (self.current.pitch - self.goal.pitch).abs() > eps;
+        if moving {
+            let t = damp(self.smoothing, dt);
+            let lerp = |a: f32, b: f32| a + (b - a) * t;
+            self.current = Fly {
+                position: Vec3::interpolate(self.current.position, self.goal.position, t),
+                yaw: lerp(self.current.yaw, self.goal.yaw),
+                pitch: lerp(self.current.pitch, self.goal.pitch),
+            };
+        } else {
+            self.current = self.goal;
+        }
+        camera.set_position(self.current.position);
+        camera.set_rotation(self.current.rotation());
+        moving
+    }
+}
@@ -0,0 +1,225 @@
+use crate::*;
+use std::collections::HashSet;
+
+//lines lying on a face have the same depth as the face, push them a little towards the camera
+const LINE_DEPTH_BIAS: f32 = 1e-4;
+
+const AXIS_X_COLOR: Color = [255, 0, 0];
+const AXIS_Y_COLOR: Color = [0, 255, 0];
+const AXIS_Z_COLOR: Color = [0, 0, 255];
+
+impl Render {
+    //start,end: (screen x, screen y, ndc z)
+    fn draw_screen_line(&mut self, start: Vec3, end: Vec3, color: Color, depth_test: bool) {
+        let dx = end.x - start.x;
+        let dy = end.y - start.y;
+        let steps = dx.abs().max(dy.abs()).ceil().max(1.0) as u32;
+
+        for i in 0..=steps {
+            let t = i as f32 / steps as f32;
+            let x = (start.x + dx * t).floor();
+            let y = (start.y + dy * t).floor();
+            if x < 0.0 || y < 0.0 {
+                continue;
+            }
+            let (x, y) = (x as u32, y as u32);
+            let Some(depth) = self.depth_buffer.get_depth(x, y) else {
+                continue;
+            };
+
+            let z = start.z + (end.z - start.z) * t + LINE_DEPTH_BIAS;
+            if depth_test {
+                if z < depth {
+                    continue;
+                }
+                self.depth_buffer.set_depth(x, y, z);
+            }
+            self.write_display_color(x, y, color);
+        }
+    }
+
+    //edges of triangles which are already mapped to screen
+    pub(crate) fn draw_triangle_edges(&mut self, triangles: &[Triangle], color: Color) {
+        triangles.iter().for_each(|triangle| {
+            let (a, b, c) = (
+                triangle.a.position,
+                triangle.b.position,
+                triangle.c.position,
+            );
+            self.draw_screen_line(a, b, color, true);
+            self.draw_screen_line(b, c, color, true);
+            self.draw_screen_line(c, a, color, true);
+        });
+    }
+
+    pub fn draw_line_3d(&mut self, start: Vec3, end: Vec3, color: Color, depth_test: bool) {
+        let view_projection = self.get_view_projection();
+        self.draw_clip_line(
+            view_projection.mul(start.upgrade()),
+            view_projection.mul(end.upgrade()),
+            color,
+            depth_test,
+        )
+    }
+
+    //Liang-Barsky clipping in clip space.
+    //camera looks at -z so w is negative in front of the camera, the frustum is |x|,|y|,|z| <= -w
+    fn draw_clip_line(&mut self, start: Vec4, end: Vec4, color: Color, depth_test: bool) {
+        let planes = |p: &Vec4| {
+            let s = -p.w;
+            [s - p.x, s + p.x, s - p.y, s + p.y, s - p.z, s + p.z]
+        };
+        let d0 = planes(&start);
+        let d1 = planes(&end);
+
+        let mut t0: f32 = 0.0;
+        let mut t1: f32 = 1.0;
+        for i in 0..6 {
+            if d0[i] < 0.0 && d1[i] < 0.0 {
+                return;
+            }
+            if d0[i] < 0.0 {
+                t0 = t0.max(d0[i] / (d0[i] - d1[i]));
+            } else if d1[i] < 0.0 {
+                t1 = t1.min(d0[i] / (d0[i] - d1[i]));
+            }
+        }
+        if t0 > t1 {
+            return;
+        }
+
+        let lerp = |t: f32| {
+            Vec4::new(
+                start.x + (end.x - start.x) * t,
+                start.y + (end.y - start.y) * t,
+                start.z + (end.z - start.z) * t,
+                start.w + (end.w - start.w) * t,
+            )
+        };
+        let mut a = lerp(t0).perspective_divide();
+        let mut b = lerp(t1).perspective_divide();
+        self.view_port_transform(&mut a);
+        self.view_port_transform(&mut b);
+        self.draw_screen_line(a, b, color, depth_test)
+    }
+
+    pub fn draw_bounding_box(
+        &mut self,
+        min: Vec3,
+        max: Vec3,
+        model_mat: &Matrix4,
+        color: Color,
+        depth_test: bool,
+    ) {
+        let corners: Vec<Vec3> = (0..8)
+            .map(|i| {
+                let corner = Vec3::new(
+                    if i & 1 == 0 { min.x } else { max.x },
+                    if i & 2 == 0 { min.y } else { max.y },
+                    if i & 4 == 0 { min.z } else { max.z },
+                );
+                model_mat.mul(corner.upgrade()).perspective_divide()
+            })
+            .collect();
+
+        //corners differ in exactly one bit share an edge
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.draw_line_3d(corners[i], corners[i | bit], color, depth_test);
+                }
+            }
+        }
+    }
+
+    //x red, y green, z blue
+    pub fn draw_axes(&mut self, model_mat: &Matrix4, length: f32, depth_test: bool) {
+        let origin = model_mat.mul(Vec3::zero().upgrade()).perspective_divide();
+        for (axis, color) in [
+            (Vec3::X, AXIS_X_COLOR),
+            (Vec3::Y, AXIS_Y_COLOR),
+            (Vec3::Z, AXIS_Z_COLOR),
+        ] {
+            let end = model_mat
+                .mul((length * axis).upgrade())
+                .perspective_divide();
+            self.draw_line_3d(origin, end, color, depth_test);
+        }
+    }
+
+    //grid on the y = height plane, centered at the origin
+    pub fn draw_grid(
+        &mut self,
+        height: f32,
+        half_size: f32,
+        step: f32,
+        color: Color,
+        depth_test: bool,
+    ) {
+        let n = (half_size / step).floor() as i32;
+        for i in -n..=n {
+            let offset = i as f32 * step;
+            self.draw_line_3d(
+                Vec3::new(offset, height, -half_size),
+                Vec3::new(offset, height, half_size),
+                color,
+                depth_test,
+            );
+            self.draw_line_3d(
+                Vec3::new(-half_size, height, offset),
+                Vec3::new(half_size, height, offset),
+                color,
+                depth_test,
+            );
+        }
+    }
+
+    pub fn draw_normals(
+        &mut self,
+        mesh: &Mesh,
+        model_mat: &Matrix4,
+        length: f32,
+        color: Color,
+        depth_test: bool,
+    ) {
+        let normal_mat = normal_matrix(model_mat);
+        mesh.get_posed_vertexes().iter().for_each(|vertex| {
+            let start = model_mat
+                .mul(vertex.position.upgrade())
+                .perspective_divide();
+            let normal = transform_normal(&normal_mat, vertex.normal);
+            self.draw_line_3d(
+                start,
+                start + length * normal.normalize(),
+                color,
+                depth_test,
+            );
+        });
+    }
+
+    //every edge of the mesh, including the back faces
+    pub fn draw_wireframe(
+        &mut self,
+        mesh: &Mesh,
+        model_mat: &Matrix4,
+        color: Color,
+        depth_test: bool,
+    ) {
+        let view_projection = self.get_view_projection() * *model_mat;
+        let clip_positions: Vec<Vec4> = mesh
+            .get_posed_vertexes()
+            .iter()
+            .map(|vertex| view_projection.mul(vertex.position.upgrade()))
+            .collect();
+
+        let mut edges = HashSet::new();
+        mesh.indies.chunks_exact(3).for_each(|face| {
+            for (a, b) in [(face[0], face[1]), (face[1], face[2]), (face[2], face[0])] {
+                edges.insert((a.min(b), a.max(b)));
+            }
+        });
+        edges.iter().for_each(|(a, b)| {
+            self.draw_clip_line(clip_positions[*a], clip_positions[*b], color, depth_test)
+        });
+    }
+}
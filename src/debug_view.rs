@@ -0,0 +1,164 @@
+use crate::*;
+
+const CHECKER_SIZE: f32 = 8.0; //checks per uv unit
+const OVERDRAW_MAX: u32 = 8; //fragment count mapped to the hottest colour
+const FRONT_FACE_COLOR: Vec3 = Vec3 {
+    x: 0.8,
+    y: 0.8,
+    z: 0.8,
+};
+const BACK_FACE_COLOR: Vec3 = Vec3 {
+    x: 1.0,
+    y: 0.0,
+    z: 0.0,
+};
+
+//built-in replacements of the fragment shader to inspect the pipeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DebugView {
+    None,
+    Depth,      //linear depth between near(black) and far(white)
+    Normal,     //world normal
+    Uv,         //texcoord as (r,g)
+    Checker,    //checkerboard in texture space
+    Overdraw,   //fragments per pixel, before depth test
+    TriangleId, //random colour per triangle
+    Backface,   //no back face culling, back faces in red
+}
+impl DebugView {
+    pub const ALL: [DebugView; 8] = [
+        DebugView::None,
+        DebugView::Depth,
+        DebugView::Normal,
+        DebugView::Uv,
+        DebugView::Checker,
+        DebugView::Overdraw,
+        DebugView::TriangleId,
+        DebugView::Backface,
+    ];
+}
+
+fn to_color(v: Vec3) -> Color {
+    [
+        (v.x.clamp(0.0, 1.0) * 255.0) as u8,
+        (v.y.clamp(0.0, 1.0) * 255.0) as u8,
+        (v.z.clamp(0.0, 1.0) * 255.0) as u8,
+    ]
+}
+
+//blue -> green -> red
+fn heatmap(count: u32) -> Color {
+    let t = (count as f32 / OVERDRAW_MAX as f32).min(1.0);
+    if t < 0.5 {
+        to_color(Vec3::new(0.0, 2.0 * t, 1.0 - 2.0 * t))
+    } else {
+        to_color(Vec3::new(2.0 * t - 1.0, 2.0 - 2.0 * t, 0.0))
+    }
+}
+
+//integer hash, so neighbour triangles get unrelated colours
+fn id_color(id: usize) -> Color {
+    let mut h = id as u32 ^ 0x9e37_79b9;
+    h = (h ^ (h >> 16)).wrapping_mul(0x7feb_352d);
+    h = (h ^ (h >> 15)).wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    [(h >> 16) as u8, (h >> 8) as u8, h as u8]
+}
+
+impl Render {
+    pub fn set_debug_view(&mut self, debug_view: DebugView) {
+        self.debug_view = debug_view
+    }
+    pub fn get_debug_view(&self) -> DebugView {
+        self.debug_view
+    }
+
+    //triangles straight from primitive assembly
+    pub(crate) fn draw_debug_view(&mut self, mut triangles: Vec<Triangle>) -> RenderStats {
+        let mut stats = RenderStats::default();
+        let stage = stage!("assembly");
+        let n_assembled = triangles.len();
+        let camera_dir = self.camera.get_dir();
+        let camera_position = self.camera.get_position();
+        let near = self.camera.get_frustum().get_near();
+        let far = self.camera.get_frustum().get_far();
+
+        let is_back = |triangle: &Triangle| triangle.get_world_normal().dot(&camera_dir) >= 0.0;
+        if self.debug_view != DebugView::Backface {
+            triangles.retain(|triangle| !is_back(triangle));
+        }
+        stats.triangles_back_face_culled = n_assembled - triangles.len();
+        triangles.retain(|triangle| !triangle.ndc_culling_test());
+        stats.triangles_ndc_culled =
+            n_assembled - stats.triangles_back_face_culled - triangles.len();
+        triangles.iter_mut().for_each(|triangle| {
+            self.view_port_transform(&mut triangle.a.position);
+            self.view_port_transform(&mut triangle.b.position);
+            self.view_port_transform(&mut triangle.c.position);
+        });
+        stats.assembly_time = stage.end();
+
+        //raster, shading and merge are interleaved here, the time is all counted as raster
+        let stage = stage!("raster");
+        stats.triangles_rasterized = triangles.len();
+        for (id, triangle) in triangles.iter().enumerate() {
+            let back = is_back(triangle);
+            for fragment in self.raster_triangle(triangle) {
+                stats.fragments_generated += 1;
+                let (x, y) = fragment.screen_pos;
+                let color = match self.debug_view {
+                    DebugView::Depth => {
+                        let distance = (fragment.world_position - camera_position).dot(&camera_dir);
+                        let linear = (distance - near) / (far - near);
+                        to_color(Vec3::new(linear, linear, linear))
+                    }
+                    DebugView::Normal => {
+                        to_color(0.5 * (fragment.world_normal + Vec3::new(1.0, 1.0, 1.0)))
+                    }
+                    DebugView::Uv => {
+                        to_color(Vec3::new(fragment.texcoord.x, fragment.texcoord.y, 0.0))
+                    }
+                    DebugView::Checker => {
+                        let u = (fragment.texcoord.x * CHECKER_SIZE).floor() as i32;
+                        let v = (fragment.texcoord.y * CHECKER_SIZE).floor() as i32;
+                        if (u + v) & 1 == 0 {
+                            [255, 255, 255]
+                        } else {
+                            [32, 32, 32]
+                        }
+                    }
+                    DebugView::Overdraw => {
+                        let count = self.overdraw_buffer.get_value(x, y).copied().unwrap_or(0) + 1;
+                        self.overdraw_buffer.set_value(x, y, count);
+                        self.write_display_color(x, y, heatmap(count));
+                        continue;
+                    }
+                    DebugView::TriangleId => id_color(id),
+                    DebugView::Backface | DebugView::None => {
+                        let facing = 0.3 + 0.7 * fragment.world_normal.dot(&camera_dir).abs();
+                        let base = if back {
+                            BACK_FACE_COLOR
+                        } else {
+                            FRONT_FACE_COLOR
+                        };
+                        to_color(facing * base)
+                    }
+                };
+
+                if self
+                    .depth_buffer
+                    .get_depth(x, y)
+                    .is_some_and(|depth| fragment.depth > depth)
+                {
+                    self.depth_buffer.set_depth(x, y, fragment.depth);
+                    self.write_display_color(x, y, color);
+                    stats.depth_test_passed += 1;
+                } else {
+                    stats.depth_test_failed += 1;
+                }
+            }
+        }
+        stats.raster_time = stage.end();
+        stats
+    }
+}
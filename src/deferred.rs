@@ -0,0 +1,227 @@
+use crate::*;
+
+const TILE_SIZE: u32 = 16;
+pub const NO_MATERIAL: u32 = u32::MAX; //material id of pixels nothing was drawn to
+
+//what the lighting pass needs from the material of a draw, maps are sampled into the g-buffer
+#[derive(Clone, Debug)]
+pub enum GBufferMaterial {
+    Phong(PhongMaterial),
+    Pbr,
+    Toon(ToonMaterial),
+}
+
+pub struct GBuffer {
+    pub position: Buffer<Vec3>, //world, the depth is in the depth buffer of the render
+    pub normal: Buffer<Vec3>,   //world, normalized
+    pub albedo: Buffer<Vec3>,   //linear
+    pub specular: Buffer<Vec3>, //phong: specular colour, pbr: (metallic, roughness, ao), toon: (u, v, 0)
+    pub emissive: Buffer<Vec3>,
+    pub material_id: Buffer<u32>, //index into `materials`
+    pub materials: Vec<GBufferMaterial>,
+}
+impl GBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            position: Buffer::new_with_capacity(width, height, Vec3::zero()),
+            normal: Buffer::new_with_capacity(width, height, Vec3::zero()),
+            albedo: Buffer::new_with_capacity(width, height, Vec3::zero()),
+            specular: Buffer::new_with_capacity(width, height, Vec3::zero()),
+            emissive: Buffer::new_with_capacity(width, height, Vec3::zero()),
+            material_id: Buffer::new_with_capacity(width, height, NO_MATERIAL),
+            materials: vec![],
+        }
+    }
+    //only the ids, every other value is overwritten together with its id
+    pub fn reset(&mut self) {
+        self.material_id.reset(NO_MATERIAL);
+        self.materials.clear();
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LightCulling {
+    None,
+    //lights with a range are only evaluated in the screen tiles their sphere overlaps
+    Tiled,
+}
+
+impl Render {
+    pub fn get_g_buffer(&self) -> Option<&GBuffer> {
+        self.g_buffer.as_ref()
+    }
+
+    //geometry pass, writes the surface into the g-buffer instead of shading it.
+    //the vertex shader of the render is used, the fragment shader is not
+    pub fn draw_deferred(&mut self, mesh: &Mesh, model_mat: Matrix4) -> RenderStats {
+        let mut stats = RenderStats::default();
+        //gouraud needs the lights in the vertex stage, here it is lit per pixel like phong
+        let triangles = self.assemble(mesh, model_mat, None, &mut stats);
+        let (_, fragments) = self.rasterize(triangles, &mut stats);
+
+        let stage = stage!("merge");
+        let g_buffer = self
+            .g_buffer
+            .get_or_insert_with(|| GBuffer::new(self.width, self.height));
+        let material_id = g_buffer.materials.len() as u32;
+        let material = &mesh.material;
+        g_buffer.materials.push(match &material.material {
+            MaterialModel::Phong(phong) => GBufferMaterial::Phong(*phong),
+            MaterialModel::Pbr(_) => GBufferMaterial::Pbr,
+            MaterialModel::Toon(toon) => GBufferMaterial::Toon(toon.clone()),
+        });
+
+        for fragment in fragments.iter() {
+            let (x, y) = fragment.screen_pos;
+            if !self
+                .depth_buffer
+                .get_depth(x, y)
+                .is_some_and(|depth| fragment.depth > depth)
+            {
+                stats.depth_test_failed += 1;
+                continue;
+            }
+            self.depth_buffer.set_depth(x, y, fragment.depth);
+            stats.depth_test_passed += 1;
+
+            //only the visible fragments sample their textures
+            let (specular, emissive) = match &material.material {
+                MaterialModel::Phong(phong) => (
+                    specular_color(material, phong, fragment.texcoord),
+                    Vec3::zero(),
+                ),
+                MaterialModel::Pbr(pbr) => {
+                    let sample = pbr.sample(fragment.texcoord);
+                    (
+                        Vec3::new(sample.metallic, sample.roughness, sample.ao),
+                        sample.emissive,
+                    )
+                }
+                //the texcoord, for the hatching
+                MaterialModel::Toon(_) => (
+                    Vec3::new(fragment.texcoord.x, fragment.texcoord.y, 0.0),
+                    Vec3::zero(),
+                ),
+            };
+            g_buffer.position.set_value(x, y, fragment.world_position);
+            g_buffer
+                .normal
+                .set_value(x, y, surface_normal(fragment, material, &model_mat));
+            g_buffer
+                .albedo
+                .set_value(x, y, base_color(material, fragment));
+            g_buffer.specular.set_value(x, y, specular);
+            g_buffer.emissive.set_value(x, y, emissive);
+            g_buffer.material_id.set_value(x, y, material_id);
+        }
+        stats.merge_time = stage.end();
+
+        self.frame_stats += stats;
+        stats
+    }
+
+    //lighting pass, every pixel of the g-buffer is shaded once with all lights
+    pub fn shade_deferred(
+        &mut self,
+        lights: &LightSet,
+        light_culling: LightCulling,
+    ) -> RenderStats {
+        let mut stats = RenderStats::default();
+        let Some(g_buffer) = self.g_buffer.take() else {
+            return stats;
+        };
+
+        let stage = stage!("fragment_shading");
+        let camera_position = self.camera.get_position();
+        let all_lights: Vec<usize> = (0..lights.lights.len()).collect();
+        let (tiles_x, tiles_y) = (
+            self.width.div_ceil(TILE_SIZE),
+            self.height.div_ceil(TILE_SIZE),
+        );
+        for tile_y in 0..tiles_y {
+            for tile_x in 0..tiles_x {
+                let xs = tile_x * TILE_SIZE..((tile_x + 1) * TILE_SIZE).min(self.width);
+                let ys = tile_y * TILE_SIZE..((tile_y + 1) * TILE_SIZE).min(self.height);
+                let pixels = || {
+                    ys.clone().flat_map({
+                        let xs = xs.clone();
+                        move |y| xs.clone().map(move |x| (x, y))
+                    })
+                };
+
+                let tile_lights = match light_culling {
+                    LightCulling::None => all_lights.clone(),
+                    LightCulling::Tiled => {
+                        //world space box around everything drawn in the tile
+                        let mut min = Vec3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY);
+                        let mut max = -min;
+                        for (x, y) in pixels() {
+                            if *g_buffer.material_id.get_value(x, y).unwrap() != NO_MATERIAL {
+                                let position = *g_buffer.position.get_value(x, y).unwrap();
+                                min = min.min(&position);
+                                max = max.max(&position);
+                            }
+                        }
+                        if min.x > max.x {
+                            continue; //empty tile
+                        }
+                        all_lights
+                            .iter()
+                            .copied()
+                            .filter(|i| match lights.lights[*i].get_bounds() {
+                                Some((center, radius)) => {
+                                    let closest = center.max(&min).min(&max);
+                                    (closest - center).length2() <= radius * radius
+                                }
+                                None => true,
+                            })
+                            .collect()
+                    }
+                };
+
+                for (x, y) in pixels() {
+                    let id = *g_buffer.material_id.get_value(x, y).unwrap();
+                    if id == NO_MATERIAL {
+                        continue;
+                    }
+                    let position = *g_buffer.position.get_value(x, y).unwrap();
+                    let surface = Surface {
+                        position,
+                        normal: *g_buffer.normal.get_value(x, y).unwrap(),
+                        view: (camera_position - position).normalize(),
+                        albedo: *g_buffer.albedo.get_value(x, y).unwrap(),
+                    };
+                    let specular = *g_buffer.specular.get_value(x, y).unwrap();
+                    let samples = tile_lights
+                        .iter()
+                        .filter_map(|i| lights.lights[*i].sample(position));
+
+                    let color = match &g_buffer.materials[id as usize] {
+                        GBufferMaterial::Phong(phong) => {
+                            shade_phong(phong, specular, &surface, lights, samples)
+                        }
+                        GBufferMaterial::Pbr => {
+                            let material = PbrSample {
+                                metallic: specular.x,
+                                roughness: specular.y,
+                                ao: specular.z,
+                                emissive: *g_buffer.emissive.get_value(x, y).unwrap(),
+                            };
+                            shade_pbr(&material, &surface, lights, samples)
+                        }
+                        GBufferMaterial::Toon(toon) => {
+                            let texcoord = Vec2::new(specular.x, specular.y);
+                            shade_toon(toon, &surface, lights, samples, texcoord)
+                        }
+                    };
+                    self.write_color(x, y, color);
+                }
+            }
+        }
+        stats.fragment_shading_time = stage.end();
+
+        self.g_buffer = Some(g_buffer);
+        self.frame_stats += stats;
+        stats
+    }
+}
@@ -0,0 +1,397 @@
+use crate::*;
+use image::open;
+use std::f32::consts::PI;
+use std::fs;
+use std::io::Read;
+use std::time::UNIX_EPOCH;
+
+const SPECULAR_WIDTH: u32 = 128; //width of the first level of the prefiltered chain
+const SPECULAR_LEVELS: usize = 6; //roughness 0, 0.2, .. 1
+const SPECULAR_SAMPLES: u32 = 64;
+const BRDF_LUT_SIZE: usize = 32;
+const BRDF_LUT_SAMPLES: u32 = 128;
+const CACHE_MAGIC: &[u8; 4] = b"IBL1";
+
+//latitude-longitude radiance map, +y is up and the top row
+pub struct EnvironmentMap {
+    width: u32,
+    height: u32,
+    data: Vec<Vec3>,
+}
+impl EnvironmentMap {
+    //.hdr, .exr or anything else `image` opens
+    pub fn load(path: &str) -> Self {
+        let buffer = open(path).unwrap().into_rgb32f();
+        let (width, height) = buffer.dimensions();
+        let data = buffer.pixels().map(|pixel| pixel.0.into()).collect();
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+    pub fn from_fn(width: u32, height: u32, radiance: impl Fn(Vec3) -> Vec3) -> Self {
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                data.push(radiance(texel_direction(x, y, width, height)));
+            }
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+    fn get_texel(&self, x: u32, y: u32) -> Vec3 {
+        self.data[(y * self.width + x) as usize]
+    }
+
+    //bilinear, wraps around horizontally
+    pub fn sample(&self, direction: Vec3) -> Vec3 {
+        let (u, v) = direction_to_uv(direction);
+        let fx = u * self.width as f32 - 0.5;
+        let fy = (v * self.height as f32 - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let (x0, y0) = (fx.floor(), fy.floor());
+        let (tx, ty) = (fx - x0, fy - y0);
+        let wrap = |x: f32| (x as i32).rem_euclid(self.width as i32) as u32;
+        let (x0, x1) = (wrap(x0), wrap(x0 + 1.0));
+        let (y0, y1) = (y0 as u32, (y0 as u32 + 1).min(self.height - 1));
+
+        let top = (1.0 - tx) * self.get_texel(x0, y0) + tx * self.get_texel(x1, y0);
+        let bottom = (1.0 - tx) * self.get_texel(x0, y1) + tx * self.get_texel(x1, y1);
+        (1.0 - ty) * top + ty * bottom
+    }
+
+    //box filter to a smaller size, keeps the prefiltering cheap
+    pub fn downsample(&self, width: u32, height: u32) -> Self {
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            let y_begin = y * self.height / height;
+            let y_end = ((y + 1) * self.height / height).max(y_begin + 1);
+            for x in 0..width {
+                let x_begin = x * self.width / width;
+                let x_end = ((x + 1) * self.width / width).max(x_begin + 1);
+                let mut sum = Vec3::zero();
+                for sy in y_begin..y_end {
+                    for sx in x_begin..x_end {
+                        sum += self.get_texel(sx, sy);
+                    }
+                }
+                let count = ((y_end - y_begin) * (x_end - x_begin)) as f32;
+                data.push((1.0 / count) * sum);
+            }
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+}
+
+fn direction_to_uv(direction: Vec3) -> (f32, f32) {
+    let phi = direction.z.atan2(direction.x);
+    let theta = direction.y.clamp(-1.0, 1.0).acos();
+    (0.5 + phi / (2.0 * PI), theta / PI)
+}
+
+fn texel_direction(x: u32, y: u32, width: u32, height: u32) -> Vec3 {
+    let phi = ((x as f32 + 0.5) / width as f32 - 0.5) * 2.0 * PI;
+    let theta = (y as f32 + 0.5) / height as f32 * PI;
+    Vec3::new(
+        theta.sin() * phi.cos(),
+        theta.cos(),
+        theta.sin() * phi.sin(),
+    )
+}
+
+//precomputed image based lighting of an environment map
+pub struct Ibl {
+    irradiance: [Vec3; 9], //order 2 spherical harmonics, already convolved with the cosine lobe
+    specular: Vec<EnvironmentMap>, //prefiltered for roughness 0..1
+    brdf_lut: Vec<Vec2>,   //(scale, bias) of f0, indexed by n·v and roughness
+}
+impl Ibl {
+    pub fn new(environment: &EnvironmentMap) -> Self {
+        let irradiance = project_sh(environment);
+
+        let base = environment.downsample(SPECULAR_WIDTH, SPECULAR_WIDTH / 2);
+        let mut specular = vec![];
+        for level in 1..SPECULAR_LEVELS {
+            let roughness = level as f32 / (SPECULAR_LEVELS - 1) as f32;
+            let width = (SPECULAR_WIDTH >> level).max(8);
+            specular.push(prefilter(&base, roughness, width, width / 2));
+        }
+        specular.insert(0, base);
+
+        Self {
+            irradiance,
+            specular,
+            brdf_lut: integrate_brdf_lut(),
+        }
+    }
+
+    //precomputed data is cached next to the map as `<path>.ibl`, rebuilt when the map changes
+    pub fn load(path: &str) -> Self {
+        let cache_path = format!("{}.ibl", path);
+        let stamp = source_stamp(path);
+        if let Some(ibl) = fs::read(&cache_path)
+            .ok()
+            .and_then(|bytes| Ibl::from_cache(&bytes, stamp))
+        {
+            return ibl;
+        }
+
+        let ibl = Ibl::new(&EnvironmentMap::load(path));
+        //a read-only asset directory only costs the precomputation next time
+        let _ = fs::write(&cache_path, ibl.to_cache(stamp));
+        ibl
+    }
+
+    //diffuse irradiance / pi around a normal, multiply by albedo for the diffuse part
+    pub fn irradiance(&self, normal: Vec3) -> Vec3 {
+        let basis = sh_basis(normal);
+        let mut irradiance = Vec3::zero();
+        for (coefficient, y) in self.irradiance.iter().zip(basis) {
+            irradiance += y * *coefficient;
+        }
+        (1.0 / PI) * irradiance.max(&Vec3::zero())
+    }
+
+    //incoming radiance along the reflection vector, blurred according to roughness
+    pub fn specular(&self, reflection: Vec3, roughness: f32) -> Vec3 {
+        let lod = roughness.clamp(0.0, 1.0) * (self.specular.len() - 1) as f32;
+        let level = (lod.floor() as usize).min(self.specular.len() - 2);
+        let t = lod - level as f32;
+        (1.0 - t) * self.specular[level].sample(reflection)
+            + t * self.specular[level + 1].sample(reflection)
+    }
+
+    //split sum approximation, specular = prefiltered * (f0 * scale + bias)
+    pub fn brdf(&self, n_dot_v: f32, roughness: f32) -> Vec2 {
+        let index = |value: f32| {
+            ((value.clamp(0.0, 1.0) * BRDF_LUT_SIZE as f32) as usize).min(BRDF_LUT_SIZE - 1)
+        };
+        self.brdf_lut[index(roughness) * BRDF_LUT_SIZE + index(n_dot_v)]
+    }
+
+    fn to_cache(&self, stamp: u64) -> Vec<u8> {
+        let mut bytes = CACHE_MAGIC.to_vec();
+        bytes.extend(stamp.to_le_bytes());
+        let push_vec3 = |bytes: &mut Vec<u8>, v: &Vec3| {
+            bytes.extend(v.x.to_le_bytes());
+            bytes.extend(v.y.to_le_bytes());
+            bytes.extend(v.z.to_le_bytes());
+        };
+        self.irradiance
+            .iter()
+            .for_each(|v| push_vec3(&mut bytes, v));
+        bytes.extend((self.specular.len() as u32).to_le_bytes());
+        for level in &self.specular {
+            bytes.extend(level.width.to_le_bytes());
+            bytes.extend(level.height.to_le_bytes());
+            level.data.iter().for_each(|v| push_vec3(&mut bytes, v));
+        }
+        for v in &self.brdf_lut {
+            bytes.extend(v.x.to_le_bytes());
+            bytes.extend(v.y.to_le_bytes());
+        }
+        bytes
+    }
+
+    //None if the cache is broken or was made from another version of the map
+    fn from_cache(mut bytes: &[u8], stamp: u64) -> Option<Self> {
+        let mut magic = [0u8; 4];
+        bytes.read_exact(&mut magic).ok()?;
+        if &magic != CACHE_MAGIC || read_u64(&mut bytes)? != stamp {
+            return None;
+        }
+
+        let mut irradiance = [Vec3::zero(); 9];
+        for v in irradiance.iter_mut() {
+            *v = read_vec3(&mut bytes)?;
+        }
+        let n_levels = read_u32(&mut bytes)? as usize;
+        if n_levels < 2 {
+            return None;
+        }
+        let mut specular = Vec::with_capacity(n_levels);
+        for _ in 0..n_levels {
+            let width = read_u32(&mut bytes)?;
+            let height = read_u32(&mut bytes)?;
+            let data = (0..width * height)
+                .map(|_| read_vec3(&mut bytes))
+                .collect::<Option<Vec<_>>>()?;
+            specular.push(EnvironmentMap {
+                width,
+                height,
+                data,
+            });
+        }
+        let brdf_lut = (0..BRDF_LUT_SIZE * BRDF_LUT_SIZE)
+            .map(|_| Some(Vec2::new(read_f32(&mut bytes)?, read_f32(&mut bytes)?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        if !bytes.is_empty() {
+            return None;
+        }
+        Some(Self {
+            irradiance,
+            specular,
+            brdf_lut,
+        })
+    }
+}
+
+//size and modification time of the source map
+fn source_stamp(path: &str) -> u64 {
+    let metadata = fs::metadata(path).unwrap();
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    metadata.len() ^ modified.rotate_left(32)
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Option<u32> {
+    let mut buffer = [0u8; 4];
+    bytes.read_exact(&mut buffer).ok()?;
+    Some(u32::from_le_bytes(buffer))
+}
+fn read_u64(bytes: &mut &[u8]) -> Option<u64> {
+    let mut buffer = [0u8; 8];
+    bytes.read_exact(&mut buffer).ok()?;
+    Some(u64::from_le_bytes(buffer))
+}
+fn read_f32(bytes: &mut &[u8]) -> Option<f32> {
+    read_u32(bytes).map(f32::from_bits)
+}
+fn read_vec3(bytes: &mut &[u8]) -> Option<Vec3> {
+    Some(Vec3::new(
+        read_f32(bytes)?,
+        read_f32(bytes)?,
+        read_f32(bytes)?,
+    ))
+}
+
+fn sh_basis(d: Vec3) -> [f32; 9] {
+    [
+        0.282095,
+        0.488603 * d.y,
+        0.488603 * d.z,
+        0.488603 * d.x,
+        1.092548 * d.x * d.y,
+        1.092548 * d.y * d.z,
+        0.315392 * (3.0 * d.z * d.z - 1.0),
+        1.092548 * d.x * d.z,
+        0.546274 * (d.x * d.x - d.y * d.y),
+    ]
+}
+
+//radiance projected on the basis, then convolved with the clamped cosine(Ramamoorthi & Hanrahan)
+fn project_sh(environment: &EnvironmentMap) -> [Vec3; 9] {
+    let (width, height) = (environment.width, environment.height);
+    let mut coefficients = [Vec3::zero(); 9];
+    for y in 0..height {
+        let theta = (y as f32 + 0.5) / height as f32 * PI;
+        let solid_angle = (2.0 * PI / width as f32) * (PI / height as f32) * theta.sin();
+        for x in 0..width {
+            let radiance = solid_angle * environment.get_texel(x, y);
+            let basis = sh_basis(texel_direction(x, y, width, height));
+            for (coefficient, y) in coefficients.iter_mut().zip(basis) {
+                *coefficient += y * radiance;
+            }
+        }
+    }
+
+    let band = [PI, 2.0 * PI / 3.0, PI / 4.0];
+    for (i, coefficient) in coefficients.iter_mut().enumerate() {
+        let l = match i {
+            0 => 0,
+            1..=3 => 1,
+            _ => 2,
+        };
+        *coefficient = band[l] * *coefficient;
+    }
+    coefficients
+}
+
+fn hammersley(i: u32, n: u32) -> Vec2 {
+    Vec2::new(i as f32 / n as f32, i.reverse_bits() as f32 / 4294967296.0)
+}
+
+//half vector around n, distributed like GGX
+fn importance_sample_ggx(xi: Vec2, n: Vec3, roughness: f32) -> Vec3 {
+    let a = roughness * roughness;
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (a * a - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+
+    let up = if n.y.abs() < 0.999 { Vec3::Y } else { Vec3::X };
+    let tangent = up.cross(&n).normalize();
+    let bitangent = n.cross(&tangent);
+    (sin_theta * phi.cos()) * tangent + (sin_theta * phi.sin()) * bitangent + cos_theta * n
+}
+
+//assumes n = v = r, so the lobe does not stretch at grazing angles
+fn prefilter(source: &EnvironmentMap, roughness: f32, width: u32, height: u32) -> EnvironmentMap {
+    EnvironmentMap::from_fn(width, height, |n| {
+        let mut sum = Vec3::zero();
+        let mut weight = 0.0;
+        for i in 0..SPECULAR_SAMPLES {
+            let h = importance_sample_ggx(hammersley(i, SPECULAR_SAMPLES), n, roughness);
+            let l = 2.0 * n.dot(&h) * h - n;
+            let n_dot_l = n.dot(&l);
+            if n_dot_l > 0.0 {
+                sum += n_dot_l * source.sample(l);
+                weight += n_dot_l;
+            }
+        }
+        (1.0 / weight.max(1e-4)) * sum
+    })
+}
+
+fn integrate_brdf_lut() -> Vec<Vec2> {
+    let mut lut = Vec::with_capacity(BRDF_LUT_SIZE * BRDF_LUT_SIZE);
+    for r in 0..BRDF_LUT_SIZE {
+        let roughness = (r as f32 + 0.5) / BRDF_LUT_SIZE as f32;
+        //k for image based lighting is a/2, not the (r+1)^2/8 of direct lights
+        let k = roughness * roughness / 2.0;
+        let schlick_ggx = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+        for v in 0..BRDF_LUT_SIZE {
+            let n_dot_v = (v as f32 + 0.5) / BRDF_LUT_SIZE as f32;
+            let view = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+
+            let (mut scale, mut bias) = (0.0, 0.0);
+            for i in 0..BRDF_LUT_SAMPLES {
+                let h = importance_sample_ggx(hammersley(i, BRDF_LUT_SAMPLES), Vec3::Z, roughness);
+                let l = 2.0 * view.dot(&h) * h - view;
+                let n_dot_l = l.z;
+                let n_dot_h = h.z.max(0.0);
+                let v_dot_h = view.dot(&h).max(0.0);
+                if n_dot_l > 0.0 {
+                    let g = schlick_ggx(n_dot_v) * schlick_ggx(n_dot_l);
+                    let g_vis = g * v_dot_h / (n_dot_h * n_dot_v).max(1e-4);
+                    let fc = (1.0 - v_dot_h).powi(5);
+                    scale += (1.0 - fc) * g_vis;
+                    bias += fc * g_vis;
+                }
+            }
+            lut.push(Vec2::new(
+                scale / BRDF_LUT_SAMPLES as f32,
+                bias / BRDF_LUT_SAMPLES as f32,
+            ));
+        }
+    }
+    lut
+}
@@ -0,0 +1,383 @@
+use crate::*;
+use gltf::animation::util::ReadOutputs;
+use gltf::animation::Property;
+use std::collections::HashMap;
+
+pub struct GltfModel {
+    pub vertexes: Vec<Vertex>,
+    pub indies: Vec<usize>,
+    pub skeleton: Option<Skeleton>,
+    pub animations: Vec<AnimationClip>, //target the joints of the skeleton
+    pub morph_targets: Vec<MorphTarget>,
+    pub morph_weights: Vec<f32>, //the default weights of the file
+}
+impl GltfModel {
+    pub fn into_mesh(self, material: Material) -> Mesh {
+        let mut mesh = Mesh::new(self.vertexes, self.indies, material);
+        mesh.skeleton = self.skeleton;
+        mesh.morph_targets = self.morph_targets;
+        mesh.morph_weights = self.morph_weights;
+        mesh
+    }
+}
+
+fn to_matrix(columns: [[f32; 4]; 4]) -> Matrix4 {
+    //gltf matrices are column major
+    let mut matrix = Matrix4::ident();
+    for (col, column) in columns.iter().enumerate() {
+        for (row, value) in column.iter().enumerate() {
+            matrix.set(row, col, *value);
+        }
+    }
+    matrix
+}
+fn to_quat(rotation: [f32; 4]) -> Quat {
+    //gltf stores the scalar part last
+    Quat::new(rotation[3], rotation[0], rotation[1], rotation[2])
+}
+fn to_transform(node: &gltf::Node) -> Transform {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    Transform::new(translation.into(), to_quat(rotation), scale.into())
+}
+
+//the meshes of the default scene merged into one. static meshes are moved to world space, skinned
+//meshes are posed by the skin of the file and its animations, files with more than one skin are
+//rejected. a file without a skin but with animations gets a skeleton of its nodes, every mesh is
+//bound to its node so the node animations move it
+pub fn load_gltf(path: &str) -> GltfModel {
+    let (document, buffers, _) = gltf::import(path).unwrap();
+    let read = |buffer: gltf::Buffer| Some(&buffers[buffer.index()].0[..]);
+
+    let mut parents = vec![None; document.nodes().len()];
+    for node in document.nodes() {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
+    }
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .unwrap();
+    let mut world = vec![Matrix4::ident(); document.nodes().len()];
+    let mut stack: Vec<(gltf::Node, Matrix4)> =
+        scene.nodes().map(|node| (node, Matrix4::ident())).collect();
+    let mut scene_nodes = vec![];
+    while let Some((node, parent)) = stack.pop() {
+        world[node.index()] = parent * Matrix4::from(to_transform(&node));
+        stack.extend(node.children().map(|child| (child, world[node.index()])));
+        scene_nodes.push(node);
+    }
+    let mesh_nodes: Vec<&gltf::Node> = scene_nodes
+        .iter()
+        .filter(|node| node.mesh().is_some())
+        .collect();
+
+    let skin = mesh_nodes.iter().find_map(|node| node.skin());
+    if let Some(skin) = &skin {
+        assert!(
+            mesh_nodes
+                .iter()
+                .filter_map(|node| node.skin())
+                .all(|other| other.index() == skin.index()),
+            "{path} has more than one skin, only one is supported"
+        );
+    }
+    //the joints of the skin, or every node for the node animations of a file without one
+    let (joint_nodes, inverse_binds): (Vec<gltf::Node>, Vec<Matrix4>) = match &skin {
+        Some(skin) => {
+            let joint_nodes: Vec<gltf::Node> = skin.joints().collect();
+            let inverse_binds = match skin.reader(read).read_inverse_bind_matrices() {
+                Some(matrices) => matrices.map(to_matrix).collect(),
+                None => vec![Matrix4::ident(); joint_nodes.len()],
+            };
+            (joint_nodes, inverse_binds)
+        }
+        None if document
+            .animations()
+            .flat_map(|animation| animation.channels())
+            .any(|channel| {
+                !matches!(channel.target().property(), Property::MorphTargetWeights)
+            }) =>
+        {
+            let inverse_binds = vec![Matrix4::ident(); scene_nodes.len()];
+            (scene_nodes.clone(), inverse_binds)
+        }
+        None => (vec![], vec![]),
+    };
+    let joint_index: HashMap<usize, usize> = joint_nodes
+        .iter()
+        .enumerate()
+        .map(|(joint, node)| (node.index(), joint))
+        .collect();
+
+    let mut root = Matrix4::ident();
+    let joints: Vec<Joint> = joint_nodes
+        .iter()
+        .zip(inverse_binds)
+        .map(|(node, inverse_bind)| {
+            let parent = parents[node.index()];
+            let parent_joint = parent.and_then(|parent| joint_index.get(&parent).copied());
+            //the nodes above the skeleton
+            if let (Some(parent), None) = (parent, parent_joint) {
+                root = world[parent];
+            }
+            Joint::new(
+                node.name().unwrap_or_default(),
+                parent_joint,
+                to_transform(node),
+                inverse_bind,
+            )
+        })
+        .collect();
+    let skeleton = (!joints.is_empty()).then(|| Skeleton::new(joints).with_root(root));
+
+    //the first morph target and the number of targets of every mesh node, one node after the other
+    let mut morph_layout: HashMap<usize, (usize, usize)> = HashMap::new();
+    for node in mesh_nodes.iter() {
+        let count = node
+            .mesh()
+            .unwrap()
+            .primitives()
+            .map(|primitive| primitive.morph_targets().len())
+            .max()
+            .unwrap_or(0);
+        let first = morph_layout.values().map(|(_, count)| count).sum();
+        morph_layout.insert(node.index(), (first, count));
+    }
+
+    let animations = document
+        .animations()
+        .map(|animation| {
+            let mut channels: HashMap<AnimationTarget, AnimationChannel> = HashMap::new();
+            for channel in animation.channels() {
+                let node = channel.target().node().index();
+                let reader = channel.reader(read);
+                let (Some(inputs), Some(outputs)) = (reader.read_inputs(), reader.read_outputs())
+                else {
+                    continue;
+                };
+                let times: Vec<f32> = inputs.collect();
+                let interpolation = match channel.sampler().interpolation() {
+                    gltf::animation::Interpolation::Step => Interpolation::Step,
+                    gltf::animation::Interpolation::Linear => Interpolation::Linear,
+                    gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+                };
+                let joint = joint_index
+                    .get(&node)
+                    .map(|joint| AnimationTarget::Joint(*joint));
+                match (outputs, joint) {
+                    (ReadOutputs::Translations(values), Some(joint)) => {
+                        channel_of(&mut channels, joint).translation = Some(track(
+                            &times,
+                            values.map(Vec3::from).collect(),
+                            interpolation,
+                        ))
+                    }
+                    (ReadOutputs::Rotations(values), Some(joint)) => {
+                        channel_of(&mut channels, joint).rotation = Some(track(
+                            &times,
+                            values.into_f32().map(to_quat).collect(),
+                            interpolation,
+                        ))
+                    }
+                    (ReadOutputs::Scales(values), Some(joint)) => {
+                        channel_of(&mut channels, joint).scale = Some(track(
+                            &times,
+                            values.map(Vec3::from).collect(),
+                            interpolation,
+                        ))
+                    }
+                    //the weights of all targets of the node at every key, one track per target
+                    (ReadOutputs::MorphTargetWeights(values), _) => {
+                        let Some(&(first, count)) = morph_layout.get(&node) else {
+                            continue;
+                        };
+                        let values: Vec<f32> = values.into_f32().collect();
+                        for i in 0..count {
+                            let weights = values.chunks_exact(count).map(|weights| weights[i]);
+                            channel_of(&mut channels, AnimationTarget::MorphTarget(first + i))
+                                .weight = Some(track(&times, weights.collect(), interpolation));
+                        }
+                    }
+                    //nodes outside of the skin
+                    _ => {}
+                }
+            }
+            let mut clip = AnimationClip::new(animation.name().unwrap_or_default());
+            clip.channels = channels.into_values().collect();
+            clip
+        })
+        .collect();
+
+    let mut vertexes = vec![];
+    let mut indies = vec![];
+    let mut morph_targets: Vec<MorphTarget> = vec![];
+    let mut morph_weights = vec![];
+    for node in mesh_nodes.iter() {
+        //bound to the joint of its node in a skeleton of the nodes
+        let rigid_joint = match skin {
+            Some(_) => None,
+            None => joint_index.get(&node.index()).map(|joint| *joint as u16),
+        };
+        //skinned meshes ignore the transform of their node
+        let model_mat = match (node.skin(), rigid_joint) {
+            (None, None) => world[node.index()],
+            _ => Matrix4::ident(),
+        };
+        let normal_mat = normal_matrix(&model_mat);
+        //the targets of the node cover its vertexes
+        let node_start = vertexes.len();
+        let (first_target, target_count) = morph_layout[&node.index()];
+        let mesh = node.mesh().unwrap();
+        let name = node
+            .name()
+            .or(mesh.name())
+            .map_or(format!("mesh {}", mesh.index()), str::to_string);
+        for primitive in mesh.primitives() {
+            let reader = primitive.reader(read);
+            let start = vertexes.len();
+            let mut texcoords = reader
+                .read_tex_coords(0)
+                .map(|texcoords| texcoords.into_f32());
+            let mut colors = reader.read_colors(0).map(|colors| colors.into_rgba_f32());
+            let mut joints = reader.read_joints(0).map(|joints| joints.into_u16());
+            let mut weights = reader.read_weights(0).map(|weights| weights.into_f32());
+            let mut normals = reader.read_normals();
+            for position in reader.read_positions().unwrap() {
+                let position = model_mat
+                    .mul(Vec3::from(position).upgrade())
+                    .perspective_divide();
+                let normal = normals.as_mut().and_then(|normals| normals.next());
+                let normal = normal.map_or(Vec3::zero(), |normal| {
+                    transform_normal(&normal_mat, Vec3::from(normal)).normalize()
+                });
+                //gltf puts the origin of the texture at the top left
+                let texcoord = texcoords
+                    .as_mut()
+                    .and_then(|texcoords| texcoords.next())
+                    .map_or(Vec2::default(), |texcoord| {
+                        Vec2::new(texcoord[0], 1.0 - texcoord[1])
+                    });
+                let mut vertex = Vertex::new(position, normal, texcoord);
+                if let Some(color) = colors.as_mut().and_then(|colors| colors.next()) {
+                    vertex = vertex.with_color(Vec4::new(color[0], color[1], color[2], color[3]));
+                }
+                if let (Some(joints), Some(weights), Some(_)) = (
+                    joints.as_mut().and_then(|joints| joints.next()),
+                    weights.as_mut().and_then(|weights| weights.next()),
+                    node.skin(),
+                ) {
+                    for (joint, weight) in joints.iter().zip(weights) {
+                        assert!(
+                            weight <= 0.0 || (*joint as usize) < joint_nodes.len(),
+                            "{path}: joint {joint} is out of the {} joints of the skin",
+                            joint_nodes.len()
+                        );
+                    }
+                    vertex = vertex.with_skin(joints, weights);
+                }
+                if let Some(joint) = rigid_joint {
+                    vertex = vertex.with_skin([joint, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]);
+                }
+                vertexes.push(vertex);
+            }
+
+            let first = indies.len();
+            match reader.read_indices() {
+                Some(indices) => {
+                    indies.extend(indices.into_u32().map(|index| start + index as usize))
+                }
+                None => indies.extend(start..vertexes.len()),
+            }
+            if normals.is_none() {
+                compute_normals(&mut vertexes[start..], &shift(&indies[first..], start));
+            }
+
+            //offsets are directions, they are moved like the normals
+            let offset = |matrix: &Matrix4, offset: [f32; 3]| {
+                let offset = matrix.mul(Vec4::new(offset[0], offset[1], offset[2], 0.0));
+                Vec3::new(offset.x, offset.y, offset.z)
+            };
+            for (i, (positions, normals, _)) in reader.read_morph_targets().enumerate() {
+                if morph_targets.len() == first_target + i {
+                    let target = MorphTarget::new(&format!("{name}.{i}"), vec![], vec![]);
+                    morph_targets.push(target.with_start(node_start));
+                }
+                let target = &mut morph_targets[first_target + i];
+                if let Some(positions) = positions {
+                    target
+                        .positions
+                        .extend(positions.map(|position| offset(&model_mat, position)));
+                }
+                if let Some(normals) = normals {
+                    target
+                        .normals
+                        .extend(normals.map(|normal| offset(&normal_mat, normal)));
+                }
+            }
+            //primitives without a target keep their shape
+            for target in morph_targets[first_target..].iter_mut() {
+                target
+                    .positions
+                    .resize(vertexes.len() - node_start, Vec3::zero());
+                target
+                    .normals
+                    .resize(vertexes.len() - node_start, Vec3::zero());
+            }
+        }
+        //the weights of the node override the ones of the mesh
+        morph_weights.resize(first_target + target_count, 0.0);
+        if let Some(weights) = node.weights().or(mesh.weights()) {
+            for (target, weight) in morph_weights[first_target..].iter_mut().zip(weights) {
+                *target = *weight;
+            }
+        }
+    }
+    morph_weights.resize(morph_targets.len(), 0.0);
+    //gltf stores the offsets, the targets keep the whole shape
+    for target in morph_targets.iter_mut() {
+        let offsets = target.positions.iter_mut().zip(target.normals.iter_mut());
+        for ((position, normal), vertex) in offsets.zip(vertexes[target.start..].iter()) {
+            *position = vertex.position + *position;
+            *normal = vertex.normal + *normal;
+        }
+    }
+
+    GltfModel {
+        vertexes,
+        indies,
+        skeleton,
+        animations,
+        morph_targets,
+        morph_weights,
+    }
+}
+
+fn channel_of(
+    channels: &mut HashMap<AnimationTarget, AnimationChannel>,
+    target: AnimationTarget,
+) -> &mut AnimationChannel {
+    channels
+        .entry(target)
+        .or_insert_with(|| AnimationChannel::new(target))
+}
+
+fn track<T: Animatable>(times: &[f32], values: Vec<T>, interpolation: Interpolation) -> Track<T> {
+    let mut track = Track::new(interpolation);
+    if interpolation == Interpolation::CubicSpline {
+        //in tangent, value, out tangent for every key
+        for (time, key) in times.iter().zip(values.chunks_exact(3)) {
+            track = track.with_cubic_key(*time, key[1], key[0], key[2]);
+        }
+    } else {
+        for (time, value) in times.iter().zip(values) {
+            track = track.with_key(*time, value);
+        }
+    }
+    track
+}
+
+//indices relative to the first vertex of a primitive
+fn shift(indies: &[usize], start: usize) -> Vec<usize> {
+    indies.iter().map(|index| index - start).collect()
+}
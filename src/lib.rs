@@ -14,3 +14,48 @@ mod shader;
 pub use shader::*;
 mod material;
 pub use material::*;
+mod text;
+pub use text::*;
+mod debug;
+mod debug_view;
+pub use debug_view::*;
+mod stats;
+pub use stats::*;
+mod light;
+pub use light::*;
+mod shading;
+pub use shading::*;
+mod environment;
+pub use environment::*;
+mod background;
+pub use background::*;
+mod color;
+pub use color::*;
+mod post;
+pub use post::*;
+mod deferred;
+pub use deferred::*;
+mod target;
+pub use target::*;
+mod toon;
+pub use toon::*;
+mod ply;
+pub use ply::*;
+mod scene;
+pub use scene::*;
+mod transform;
+pub use transform::*;
+mod animation;
+pub use animation::*;
+mod skin;
+pub use skin::*;
+mod gltf_model;
+pub use gltf_model::*;
+mod morph;
+pub use morph::*;
+mod controller;
+pub use controller::*;
+mod camera_path;
+pub use camera_path::*;
+mod bounds;
+pub use bounds::*;
@@ -0,0 +1,292 @@
+use crate::*;
+use std::rc::Rc;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Attenuation {
+    //intensity / d^2
+    InverseSquare,
+    //intensity / (c + l*d + q*d^2)
+    Polynomial {
+        constant: f32,
+        linear: f32,
+        quadratic: f32,
+    },
+    //inverse square with a smooth window, exactly zero beyond the range
+    Range(f32),
+}
+impl Attenuation {
+    pub fn factor(&self, distance: f32) -> f32 {
+        match *self {
+            Attenuation::InverseSquare => 1.0 / (distance * distance),
+            Attenuation::Polynomial {
+                constant,
+                linear,
+                quadratic,
+            } => 1.0 / (constant + linear * distance + quadratic * distance * distance),
+            Attenuation::Range(range) => {
+                //(1-(d/r)^4)^2 / (d^2+1)
+                let ratio = distance / range;
+                let window = (1.0 - ratio.powi(4)).clamp(0.0, 1.0);
+                window * window / (distance * distance + 1.0)
+            }
+        }
+    }
+
+    //distance beyond which the light contributes nothing
+    pub fn get_range(&self) -> Option<f32> {
+        match self {
+            Attenuation::Range(range) => Some(*range),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct PointLight {
+    pub position: Vec3,
+    pub intensity: f32,
+    pub color: Vec3,
+    pub attenuation: Attenuation,
+}
+impl PointLight {
+    pub fn new(position: Vec3, intensity: f32) -> Self {
+        Self {
+            position,
+            intensity,
+            color: Vec3::new(1.0, 1.0, 1.0),
+            attenuation: Attenuation::InverseSquare,
+        }
+    }
+    pub fn with_color(mut self, color: Vec3) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+}
+
+//infinitely far away light, e.g. the sun
+#[derive(Clone, Copy, Debug)]
+pub struct DirectionalLight {
+    pub direction: Vec3, //direction the light travels
+    pub intensity: f32,
+    pub color: Vec3,
+}
+impl DirectionalLight {
+    pub fn new(direction: Vec3, intensity: f32) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+            color: Vec3::new(1.0, 1.0, 1.0),
+        }
+    }
+    pub fn with_color(mut self, color: Vec3) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SpotLight {
+    pub position: Vec3,
+    pub direction: Vec3,
+    pub inner_angle: f32, //half angle in radian, full intensity inside
+    pub outer_angle: f32, //half angle in radian, no light outside
+    pub intensity: f32,
+    pub color: Vec3,
+    pub attenuation: Attenuation,
+}
+impl SpotLight {
+    pub fn new(
+        position: Vec3,
+        direction: Vec3,
+        inner_angle: f32,
+        outer_angle: f32,
+        intensity: f32,
+    ) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+            intensity,
+            color: Vec3::new(1.0, 1.0, 1.0),
+            attenuation: Attenuation::InverseSquare,
+        }
+    }
+    pub fn with_color(mut self, color: Vec3) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn with_attenuation(mut self, attenuation: Attenuation) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    //1 inside the inner cone, 0 outside the outer cone, smooth in between
+    pub fn cone_factor(&self, light_to_surface: Vec3) -> f32 {
+        let cos = self.direction.dot(&light_to_surface);
+        let cos_inner = self.inner_angle.cos();
+        let cos_outer = self.outer_angle.cos();
+        let t = ((cos - cos_outer) / (cos_inner - cos_outer).max(1e-4)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Light {
+    Point(PointLight),
+    Directional(DirectionalLight),
+    Spot(SpotLight),
+}
+impl Light {
+    //incoming light at a world position, None if the position is not lit at all
+    pub fn sample(&self, position: Vec3) -> Option<LightSample> {
+        let sample = match self {
+            Light::Point(light) => {
+                let diff = light.position - position;
+                let distance = diff.length2().sqrt();
+                let attenuation = light.attenuation.factor(distance);
+                LightSample::new(
+                    diff.normalize(),
+                    distance,
+                    (light.intensity * attenuation) * light.color,
+                )
+            }
+            Light::Directional(light) => LightSample::new(
+                -light.direction,
+                f32::INFINITY,
+                light.intensity * light.color,
+            ),
+            Light::Spot(light) => {
+                let diff = light.position - position;
+                let distance = diff.length2().sqrt();
+                let direction = diff.normalize();
+                let attenuation =
+                    light.attenuation.factor(distance) * light.cone_factor(-direction);
+                LightSample::new(
+                    direction,
+                    distance,
+                    (light.intensity * attenuation) * light.color,
+                )
+            }
+        };
+
+        if sample.radiance.x > 0.0 || sample.radiance.y > 0.0 || sample.radiance.z > 0.0 {
+            Some(sample)
+        } else {
+            None
+        }
+    }
+
+    //the same light in the space `model_mat` maps to, e.g. the world space of a scene node
+    pub fn transform(&self, model_mat: &Matrix4) -> Light {
+        let point = |p: Vec3| model_mat.mul(p.upgrade()).perspective_divide();
+        let vector = |v: Vec3| {
+            let v = model_mat.mul(Vec4::new(v.x, v.y, v.z, 0.0));
+            Vec3::new(v.x, v.y, v.z).normalize()
+        };
+        let mut light = *self;
+        match &mut light {
+            Light::Point(point_light) => point_light.position = point(point_light.position),
+            Light::Directional(directional) => {
+                directional.direction = vector(directional.direction)
+            }
+            Light::Spot(spot) => {
+                spot.position = point(spot.position);
+                spot.direction = vector(spot.direction);
+            }
+        }
+        light
+    }
+
+    //sphere the light can't reach outside of, None for lights reaching everywhere
+    pub fn get_bounds(&self) -> Option<(Vec3, f32)> {
+        match self {
+            Light::Point(light) => light
+                .attenuation
+                .get_range()
+                .map(|range| (light.position, range)),
+            Light::Spot(light) => light
+                .attenuation
+                .get_range()
+                .map(|range| (light.position, range)),
+            Light::Directional(_) => None,
+        }
+    }
+}
+impl From<PointLight> for Light {
+    fn from(light: PointLight) -> Self {
+        Light::Point(light)
+    }
+}
+impl From<DirectionalLight> for Light {
+    fn from(light: DirectionalLight) -> Self {
+        Light::Directional(light)
+    }
+}
+impl From<SpotLight> for Light {
+    fn from(light: SpotLight) -> Self {
+        Light::Spot(light)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct LightSample {
+    pub direction: Vec3, //surface -> light, normalized
+    pub distance: f32,   //infinity for directional lights
+    pub radiance: Vec3,  //color * intensity * attenuation
+}
+impl LightSample {
+    pub fn new(direction: Vec3, distance: f32, radiance: Vec3) -> Self {
+        Self {
+            direction,
+            distance,
+            radiance,
+        }
+    }
+}
+
+//every light of a draw, shaders loop over it
+#[derive(Clone)]
+pub struct LightSet {
+    pub ambient: Vec3,
+    pub lights: Vec<Light>,
+    pub environment: Option<Rc<Ibl>>, //replaces the constant ambient when set, shared by the copies
+}
+impl LightSet {
+    pub fn new(ambient: Vec3) -> Self {
+        Self {
+            ambient,
+            lights: vec![],
+            environment: None,
+        }
+    }
+    pub fn with_environment(mut self, environment: Ibl) -> Self {
+        self.environment = Some(Rc::new(environment));
+        self
+    }
+    pub fn add(&mut self, light: impl Into<Light>) {
+        self.lights.push(light.into())
+    }
+    pub fn with(mut self, light: impl Into<Light>) -> Self {
+        self.add(light);
+        self
+    }
+
+    //diffuse ambient light arriving around a normal
+    pub fn get_ambient(&self, normal: Vec3) -> Vec3 {
+        match &self.environment {
+            Some(environment) => environment.irradiance(normal),
+            None => self.ambient,
+        }
+    }
+
+    pub fn samples(&self, position: Vec3) -> impl Iterator<Item = LightSample> + '_ {
+        self.lights
+            .iter()
+            .filter_map(move |light| light.sample(position))
+    }
+}
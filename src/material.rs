@@ -1,18 +1,6 @@
-use crate::{BaseColor, NormalMap, SpecularMap, Vec3};
-
-pub struct PointLight {
-    pub position: Vec3,
-    pub intensity: f32,
-}
-impl PointLight {
-    pub fn new(position: Vec3, intensity: f32) -> Self {
-        Self {
-            position,
-            intensity,
-        }
-    }
-}
+use crate::{BaseColor, EmissiveMap, NormalMap, ScalarMap, SpecularMap, ToonMaterial, Vec2, Vec3};
 
+#[derive(Clone, Copy, Debug)]
 pub struct PhongMaterial {
     pub ambient: Vec3,
     pub diffuse: Vec3,
@@ -40,22 +28,128 @@ impl Default for PhongMaterial {
     }
 }
 
+//metallic-roughness model, the base colour is `Material::base` * `base_color`
+pub struct PbrMaterial {
+    pub base_color: Vec3,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub ao: f32,
+    pub emissive: Vec3,
+    pub metallic_map: Option<ScalarMap>,
+    pub roughness_map: Option<ScalarMap>,
+    pub ao_map: Option<ScalarMap>,
+    pub emissive_map: Option<EmissiveMap>,
+}
+impl PbrMaterial {
+    pub fn new(base_color: Vec3, metallic: f32, roughness: f32) -> Self {
+        Self {
+            base_color,
+            metallic,
+            roughness,
+            ao: 1.0,
+            emissive: Vec3::zero(),
+            metallic_map: None,
+            roughness_map: None,
+            ao_map: None,
+            emissive_map: None,
+        }
+    }
+    pub fn with_ao(mut self, ao: f32) -> Self {
+        self.ao = ao;
+        self
+    }
+    pub fn with_emissive(mut self, emissive: Vec3) -> Self {
+        self.emissive = emissive;
+        self
+    }
+    pub fn with_metallic_map(mut self, map: ScalarMap) -> Self {
+        self.metallic_map = Some(map);
+        self
+    }
+    pub fn with_roughness_map(mut self, map: ScalarMap) -> Self {
+        self.roughness_map = Some(map);
+        self
+    }
+    pub fn with_ao_map(mut self, map: ScalarMap) -> Self {
+        self.ao_map = Some(map);
+        self
+    }
+    pub fn with_emissive_map(mut self, map: EmissiveMap) -> Self {
+        self.emissive_map = Some(map);
+        self
+    }
+
+    //factors multiplied with the maps
+    pub fn sample(&self, texcoord: Vec2) -> PbrSample {
+        let scalar = |factor: f32, map: &Option<ScalarMap>| match map {
+            Some(map) => factor * map.get_value(texcoord),
+            None => factor,
+        };
+        let emissive = match &self.emissive_map {
+            Some(map) => self.emissive.hadamard(&map.get_emissive(texcoord)),
+            None => self.emissive,
+        };
+        PbrSample {
+            metallic: scalar(self.metallic, &self.metallic_map),
+            //keep a little roughness, a perfect mirror only reflects point lights in a single pixel
+            roughness: scalar(self.roughness, &self.roughness_map).clamp(0.04, 1.0),
+            ao: scalar(self.ao, &self.ao_map),
+            emissive,
+        }
+    }
+}
+
+//pbr parameters at one point of the surface
+#[derive(Clone, Copy, Debug)]
+pub struct PbrSample {
+    pub metallic: f32,
+    pub roughness: f32,
+    pub ao: f32,
+    pub emissive: Vec3,
+}
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        PbrMaterial::new(Vec3::new(1.0, 1.0, 1.0), 0.0, 0.5)
+    }
+}
+
+pub enum MaterialModel {
+    Phong(PhongMaterial),
+    Pbr(PbrMaterial),
+    Toon(ToonMaterial),
+}
+impl From<PhongMaterial> for MaterialModel {
+    fn from(material: PhongMaterial) -> Self {
+        MaterialModel::Phong(material)
+    }
+}
+impl From<PbrMaterial> for MaterialModel {
+    fn from(material: PbrMaterial) -> Self {
+        MaterialModel::Pbr(material)
+    }
+}
+
+impl From<ToonMaterial> for MaterialModel {
+    fn from(material: ToonMaterial) -> Self {
+        MaterialModel::Toon(material)
+    }
+}
+
 pub struct Material {
-    // light: PointLight,
-    pub material: PhongMaterial,
+    pub material: MaterialModel,
     pub base: BaseColor,
     pub normal: Option<NormalMap>,
-    pub specular: Option<SpecularMap>,
+    pub specular: Option<SpecularMap>, //phong only
 }
 impl Material {
     pub fn new(
-        material: PhongMaterial,
+        material: impl Into<MaterialModel>,
         base: BaseColor,
         normal: Option<NormalMap>,
         specular: Option<SpecularMap>,
     ) -> Self {
         Self {
-            material,
+            material: material.into(),
             base,
             normal,
             specular,
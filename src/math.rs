@@ -99,6 +99,11 @@ impl Vec3 {
     pub fn length2(&self)->f32{
         self.dot(self)
     }
+
+    //element wise product, e.g. light color * surface color
+    pub fn hadamard(&self, rhs: &Self) -> Self {
+        Self::new(self.x * rhs.x, self.y * rhs.y, self.z * rhs.z)
+    }
 }
 impl From<[f32; 3]> for Vec3 {
     fn from(value: [f32; 3]) -> Self {
@@ -185,6 +190,12 @@ impl Mul<Vec4> for f32 {
         Vec4::new(rhs.x * self, rhs.y * self, rhs.z * self, rhs.w * self)
     }
 }
+impl Add<Vec4>for Vec4{
+    type Output = Vec4;
+    fn add(self, rhs: Vec4) -> Self::Output {
+        Vec4::new(self.x+rhs.x, self.y+rhs.y, self.z+rhs.z, self.w+rhs.w)
+    }
+}
 
 #[derive(Clone, Copy)]
 pub struct Matrix3 {
@@ -261,6 +272,33 @@ impl Matrix4 {
 
     }
 
+    #[rustfmt::skip]
+    pub fn scale(scale:Vec3)->Self{
+        Self::new([
+            scale.x, 0.0, 0.0, 0.0,
+            0.0, scale.y, 0.0, 0.0,
+            0.0, 0.0, scale.z, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ])
+    }
+    //单位四元数
+    pub fn from_quat(rotation:Quat)->Self{
+        rotation.get_rotation()
+    }
+    //view矩阵 世界坐标->相机坐标 相机朝向-z
+    #[rustfmt::skip]
+    pub fn look_at(eye:Vec3,target:Vec3,up:Vec3)->Self{
+        let z_axis=(eye-target).normalize();
+        let x_axis=up.cross(&z_axis).normalize();
+        let y_axis=z_axis.cross(&x_axis);
+        Self::new([
+            x_axis.x, x_axis.y, x_axis.z, -x_axis.dot(&eye),
+            y_axis.x, y_axis.y, y_axis.z, -y_axis.dot(&eye),
+            z_axis.x, z_axis.y, z_axis.z, -z_axis.dot(&eye),
+            0.0, 0.0, 0.0, 1.0,
+        ])
+    }
+
     pub fn transpose(&self)->Self{
         Self::new([
             self.get(0, 0),self.get(1, 0),self.get(2, 0),self.get(3, 0),
@@ -277,13 +315,37 @@ impl Matrix4 {
         -self.get(3, 0)*Matrix3::new([self.get(0, 1),self.get(0, 2),self.get(0, 3),self.get(1, 1),self.get(1, 2),self.get(1, 3),self.get(2, 1),self.get(2, 2),self.get(2, 3),]).det()
     }
 
+    //余子式 去掉row行col列
+    fn minor(&self,row:usize,col:usize)->f32{
+        let mut elements=[0.0;9];
+        let mut i=0;
+        for r in (0..4).filter(|r|*r!=row){
+            for c in (0..4).filter(|c|*c!=col){
+                elements[i]=self.get(r, c);
+                i+=1;
+            }
+        }
+        Matrix3::new(elements).det()
+    }
+
+    //伴随矩阵/行列式
+    //singular relative to the size of the rows, |det| <= product of the row lengths, so scaled
+    //matrices like an orthographic projection of a big volume are still inverted
     pub fn inv(&self)->Option<Self>{
         let det = self.det();
-        if det<1e-6{
+        let scale:f32=(0..4).map(|row|(0..4).map(|col|self.get(row, col).powi(2)).sum::<f32>().sqrt()).product();
+        if det.abs()<=1e-6*scale{
             return None
         }
 
-        Some((1.0/det)*self.transpose() )
+        let mut adjugate=Matrix4::ident();
+        for row in 0..4{
+            for col in 0..4{
+                let sign=if (row+col)%2==0 {1.0} else {-1.0};
+                adjugate.set(col, row, sign*self.minor(row, col));
+            }
+        }
+        Some((1.0/det)*adjugate)
     }
 }
 impl Mul<Vec4> for Matrix4 {
@@ -404,17 +466,61 @@ impl Quat{
     pub fn new(x:f32,y:f32,z:f32,w:f32)->Self{
         Self { x, y, z, w }
     }
-    pub fn inv(&self)->Self{
+    pub fn identity()->Self{
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+    //共轭 单位四元数的逆
+    pub fn conjugate(&self)->Self{
         Self::new(self.x, -self.y, -self.z, -self.w)
     }
+    pub fn inv(&self)->Self{
+        let conjugate=self.conjugate();
+        let l=1.0/self.length2();
+        Self::new(conjugate.x*l, conjugate.y*l, conjugate.z*l, conjugate.w*l)
+    }
+    pub fn dot(&self,rhs:&Quat)->f32{
+        self.x*rhs.x+self.y*rhs.y+self.z*rhs.z+self.w*rhs.w
+    }
+    pub fn length2(&self)->f32{
+        self.dot(self)
+    }
+    pub fn normalize(&self)->Self{
+        let l=1.0/self.length2().sqrt();
+        Self::new(self.x*l, self.y*l, self.z*l, self.w*l)
+    }
+    //虚部
+    pub fn vector(&self)->Vec3{
+        Vec3::new(self.y, self.z, self.w)
+    }
+    //列向量是旋转后的坐标轴
     pub fn from_to_mat3(x_axis:Vec3,y_axis:Vec3,z_axis:Vec3)->Self{
-        let tr=x_axis.x+y_axis.y+z_axis.z;
-        let a = (tr+1.0).sqrt()*0.5;
-        let w_inv=1.0/(4.0*a);
-        let b =(y_axis.z-z_axis.y)*w_inv;
-        let c=(z_axis.x-x_axis.z)*w_inv;
-             let d=(x_axis.y-y_axis.x)*w_inv;
-             Self::new(a, b, c, d)
+        #[rustfmt::skip]
+        let m=Matrix4::new([
+            x_axis.x, y_axis.x, z_axis.x, 0.0,
+            x_axis.y, y_axis.y, z_axis.y, 0.0,
+            x_axis.z, y_axis.z, z_axis.z, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        ]);
+        Self::from_mat4(&m)
+    }
+    //左上3x3的旋转部分 取迹和对角线中最大的分支 避免除以接近0的数
+    pub fn from_mat4(m:&Matrix4)->Self{
+        let (m00,m11,m22)=(m.get(0,0),m.get(1,1),m.get(2,2));
+        let tr=m00+m11+m22;
+        let q = if tr>0.0{
+            let s=(tr+1.0).sqrt()*2.0;//4a
+            Self::new(0.25*s, (m.get(2,1)-m.get(1,2))/s, (m.get(0,2)-m.get(2,0))/s, (m.get(1,0)-m.get(0,1))/s)
+        }else if m00>m11&&m00>m22{
+            let s=(1.0+m00-m11-m22).sqrt()*2.0;//4b
+            Self::new((m.get(2,1)-m.get(1,2))/s, 0.25*s, (m.get(0,1)+m.get(1,0))/s, (m.get(0,2)+m.get(2,0))/s)
+        }else if m11>m22{
+            let s=(1.0+m11-m00-m22).sqrt()*2.0;//4c
+            Self::new((m.get(0,2)-m.get(2,0))/s, (m.get(0,1)+m.get(1,0))/s, 0.25*s, (m.get(1,2)+m.get(2,1))/s)
+        }else{
+            let s=(1.0+m22-m00-m11).sqrt()*2.0;//4d
+            Self::new((m.get(1,0)-m.get(0,1))/s, (m.get(0,2)+m.get(2,0))/s, (m.get(1,2)+m.get(2,1))/s, 0.25*s)
+        };
+        q.normalize()
     }
     pub fn from_axis_angle(axis:Vec3,radian:f32)->Self{
         let axis=axis.normalize();
@@ -423,6 +529,128 @@ impl Quat{
 
         Quat::new(cos, sin*axis.x, sin*axis.y, sin*axis.z)
     }
+    //角度在[0,pi] 没有旋转时轴取x
+    pub fn to_axis_angle(&self)->(Vec3,f32){
+        let q=self.normalize();
+        let q=if q.x<0.0{-q}else{q};
+        let sin=q.vector().length2().sqrt();
+        let angle=2.0*sin.atan2(q.x);
+        if sin<1e-6{
+            return (Vec3::X,angle);
+        }
+        ((1.0/sin)*q.vector(),angle)
+    }
+    //把from转到to的最短旋转
+    pub fn from_rotation_arc(from:Vec3,to:Vec3)->Self{
+        let (from,to)=(from.normalize(),to.normalize());
+        let d=from.dot(&to);
+        if d< -1.0+1e-6{
+            //反向 绕任意垂直轴转180度
+            let mut axis=Vec3::X.cross(&from);
+            if axis.length2()<1e-6{
+                axis=Vec3::Y.cross(&from);
+            }
+            return Self::from_axis_angle(axis, std::f32::consts::PI);
+        }
+        let axis=from.cross(&to);
+        Self::new(1.0+d, axis.x, axis.y, axis.z).normalize()
+    }
+    //order是旋转的先后顺序 angles是绕x,y,z的弧度
+    pub fn from_euler(order:EulerOrder,angles:Vec3)->Self{
+        let [i,j,k]=order.axes();
+        let angle=|axis:usize|[angles.x,angles.y,angles.z][axis];
+        let axis=|axis:usize|[Vec3::X,Vec3::Y,Vec3::Z][axis];
+        Self::from_axis_angle(axis(k), angle(k))*Self::from_axis_angle(axis(j), angle(j))*Self::from_axis_angle(axis(i), angle(i))
+    }
+    //中间的角在[-pi/2,pi/2] 万向锁时第三个角取0
+    pub fn to_euler(&self,order:EulerOrder)->Vec3{
+        let [i,j,k]=order.axes();
+        let m=self.normalize().get_rotation();
+        //奇排列时符号相反
+        let s=if (j+3-i)%3==1{1.0}else{-1.0};
+        let sin_b=(-s*m.get(k,i)).clamp(-1.0, 1.0);
+        let b=sin_b.asin();
+        let (a,c)=if sin_b.abs()<0.99999{
+            ((s*m.get(k,j)).atan2(m.get(k,k)),(s*m.get(j,i)).atan2(m.get(i,i)))
+        }else{
+            ((-s*m.get(j,k)).atan2(m.get(j,j)),0.0)
+        };
+        let mut angles=[0.0;3];
+        angles[i]=a;
+        angles[j]=b;
+        angles[k]=c;
+        Vec3::new(angles[0], angles[1], angles[2])
+    }
+    //单位四元数 等于q*v*q^-1
+    pub fn rotate(&self,v:Vec3)->Vec3{
+        let u=self.vector();
+        let t=2.0*u.cross(&v);
+        v+self.x*t+u.cross(&t)
+    }
+
+    //插值都走最短路径
+    pub fn nlerp(from:Quat,to:Quat,t:f32)->Self{
+        let to=if from.dot(&to)<0.0{-to}else{to};
+        Self::new(
+            from.x+(to.x-from.x)*t,
+            from.y+(to.y-from.y)*t,
+            from.z+(to.z-from.z)*t,
+            from.w+(to.w-from.w)*t,
+        ).normalize()
+    }
+    //匀角速度
+    pub fn slerp(from:Quat,to:Quat,t:f32)->Self{
+        let to=if from.dot(&to)<0.0{-to}else{to};
+        let d=from.dot(&to);
+        //夹角很小时sin接近0 退化成nlerp
+        if d>0.9995{
+            return Self::nlerp(from, to, t);
+        }
+        let theta=d.clamp(-1.0, 1.0).acos();
+        let sin=theta.sin();
+        let a=((1.0-t)*theta).sin()/sin;
+        let b=(t*theta).sin()/sin;
+        Self::new(
+            a*from.x+b*to.x,
+            a*from.y+b*to.y,
+            a*from.z+b*to.z,
+            a*from.w+b*to.w,
+        )
+    }
+    //q1到q2的样条插值 a,b是squad_control(q0,q1,q2)和squad_control(q1,q2,q3)
+    pub fn squad(q1:Quat,q2:Quat,a:Quat,b:Quat,t:f32)->Self{
+        Self::slerp(Self::slerp(q1, q2, t), Self::slerp(a, b, t), 2.0*t*(1.0-t))
+    }
+    //q的控制点 使相邻两段在q处切线连续
+    pub fn squad_control(prev:Quat,q:Quat,next:Quat)->Self{
+        let inv=q.conjugate();
+        //相邻的取同一半球
+        let prev=if prev.dot(&q)<0.0{-prev}else{prev};
+        let next=if next.dot(&q)<0.0{-next}else{next};
+        let a=(inv*next).log();
+        let b=(inv*prev).log();
+        let sum=-0.25*(a+b);
+        q*Self::new(0.0, sum.x, sum.y, sum.z).exp()
+    }
+    //单位四元数的对数 纯虚四元数 返回虚部
+    pub fn log(&self)->Vec3{
+        let v=self.vector();
+        let sin=v.length2().sqrt();
+        if sin<1e-6{
+            return v;
+        }
+        (sin.atan2(self.x)/sin)*v
+    }
+    //纯虚四元数的指数
+    pub fn exp(&self)->Self{
+        let v=self.vector();
+        let theta=v.length2().sqrt();
+        if theta<1e-6{
+            return Self::new(1.0, v.x, v.y, v.z).normalize();
+        }
+        let s=theta.sin()/theta;
+        Self::new(theta.cos(), s*v.x, s*v.y, s*v.z)
+    }
     #[rustfmt::skip]
     pub fn get_rotation(&self)->Matrix4{
         let a = self.x;
@@ -459,6 +687,48 @@ impl Quat{
     }
 }
 
+//欧拉角的旋转顺序 XYZ: 先绕x 再绕y 最后绕z(外旋)
+#[derive(Default,Clone,Copy,Debug,PartialEq,Eq)]
+pub enum EulerOrder{
+    #[default]
+    XYZ,
+    XZY,
+    YXZ,
+    YZX,
+    ZXY,
+    ZYX,
+}
+impl EulerOrder{
+    fn axes(&self)->[usize;3]{
+        match self {
+            EulerOrder::XYZ=>[0,1,2],
+            EulerOrder::XZY=>[0,2,1],
+            EulerOrder::YXZ=>[1,0,2],
+            EulerOrder::YZX=>[1,2,0],
+            EulerOrder::ZXY=>[2,0,1],
+            EulerOrder::ZYX=>[2,1,0],
+        }
+    }
+}
+
+impl Add<Quat> for Quat{
+    type Output = Self;
+    fn add(self, rhs: Quat) -> Self::Output {
+        Quat::new(self.x+rhs.x, self.y+rhs.y, self.z+rhs.z, self.w+rhs.w)
+    }
+}
+impl Mul<Quat> for f32{
+    type Output = Quat;
+    fn mul(self, rhs: Quat) -> Self::Output {
+        Quat::new(self*rhs.x, self*rhs.y, self*rhs.z, self*rhs.w)
+    }
+}
+impl Neg for Quat{
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Quat::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
 impl Mul<Quat> for Quat{
     type Output = Self;
     fn mul(self, rhs: Quat) -> Self::Output {
@@ -1,13 +1,28 @@
 use crate::*;
 
-use obj::{load_obj, Obj, TexturedVertex};
-use std::fs::File;
-use std::io::BufReader;
+use obj::raw::object::Polygon;
+use obj::raw::parse_obj;
+use obj::{Obj, TexturedVertex};
+use std::collections::HashSet;
+use std::fs::read_to_string;
+
+//where the lighting is evaluated
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ShadingModel {
+    Flat,    //per fragment with the face normal
+    Gouraud, //per vertex, the light is interpolated
+    #[default]
+    Phong, //per fragment with the interpolated or mapped normal
+}
 
 pub struct Mesh {
     pub vertexes: Vec<Vertex>,
     pub indies: Vec<usize>,
     pub material: Material,
+    pub shading: ShadingModel,
+    pub skeleton: Option<Skeleton>, //skins the vertexes before the vertex shader
+    pub morph_targets: Vec<MorphTarget>,
+    pub morph_weights: Vec<f32>, //one per target, blended before the skinning
 }
 
 impl Mesh {
@@ -16,33 +31,90 @@ impl Mesh {
             vertexes,
             indies,
             material,
+            shading: ShadingModel::default(),
+            skeleton: None,
+            morph_targets: vec![],
+            morph_weights: vec![],
         }
     }
+    pub fn with_shading(mut self, shading: ShadingModel) -> Self {
+        self.shading = shading;
+        self
+    }
+    pub fn with_skeleton(mut self, skeleton: Skeleton) -> Self {
+        self.skeleton = Some(skeleton);
+        self
+    }
+    //min and max of the rest pose, see `get_aabb` for the posed mesh
     pub fn get_bounding(&self) -> (Vec3, Vec3) {
-        let mut min = self.vertexes[0].position;
-        let mut max = self.vertexes[0].position;
-        self.vertexes.iter().for_each(|vertex| {
-            min = vertex.position.min(&min);
-            max = vertex.position.max(&max);
-        });
-
-        (min, max)
+        let aabb = Aabb::from_vertexes(&self.vertexes).unwrap();
+        (aabb.min, aabb.max)
     }
 }
 
+//obj or ply, by the extension
 pub fn load_model(path: &str) -> (Vec<Vertex>, Vec<usize>) {
-    let input = BufReader::new(File::open(path).unwrap());
-    let model: Obj<TexturedVertex> = load_obj(input).unwrap();
+    if path.ends_with(".ply") {
+        return load_ply(path);
+    }
+    let source = read_to_string(path).unwrap();
+
+    //obj-rs only accepts `v x y z [w]`, the colours of `v x y z r g b [a]` are cut off here
+    //and kept by the index of the `v` line
+    let mut colors = vec![];
+    let source: String = source
+        .lines()
+        .map(|line| {
+            let args: Vec<&str> = line.split_whitespace().collect();
+            if args.first() != Some(&"v") {
+                return line.to_string() + "\n";
+            }
+            if args.len() < 7 {
+                colors.push(None);
+                return line.to_string() + "\n";
+            }
+            let value = |i: usize| args[i].parse::<f32>().unwrap();
+            let alpha = args.get(7).map_or(1.0, |_| value(7));
+            colors.push(Some(Vec4::new(
+                srgb_to_linear(value(4)),
+                srgb_to_linear(value(5)),
+                srgb_to_linear(value(6)),
+                alpha,
+            )));
+            args[..4].join(" ") + "\n"
+        })
+        .collect();
+    let raw = parse_obj(source.as_bytes()).unwrap();
+
+    //obj-rs makes a vertex for every new (v, vt, vn) of the faces in order,
+    //the same walk gives the `v` index of each of them
+    let mut seen = HashSet::new();
+    let mut position_indies = vec![];
+    for polygon in &raw.polygons {
+        if let Polygon::PTN(corners) = polygon {
+            for &corner in corners {
+                if seen.insert(corner) {
+                    position_indies.push(corner.0);
+                }
+            }
+        }
+    }
+    let model: Obj<TexturedVertex> = Obj::new(raw).unwrap();
 
     let vertexes = model
         .vertices
         .iter()
-        .map(|vertex| {
-            Vertex::new(
+        .zip(position_indies)
+        .map(|(vertex, position_index)| {
+            let vertex = Vertex::new(
                 vertex.position.into(),
                 vertex.normal.into(),
                 Vec3::from(vertex.texture).downgrade(),
-            )
+            );
+            match colors[position_index] {
+                Some(color) => vertex.with_color(color),
+                None => vertex,
+            }
         })
         .collect();
 
@@ -50,11 +122,38 @@ pub fn load_model(path: &str) -> (Vec<Vertex>, Vec<usize>) {
     (vertexes, indies)
 }
 
+//average of the faces around every vertex, for files without normals
+pub(crate) fn compute_normals(vertexes: &mut [Vertex], indies: &[usize]) {
+    for vertex in vertexes.iter_mut() {
+        vertex.normal = Vec3::zero();
+    }
+    for triangle in indies.chunks_exact(3) {
+        let (a, b, c) = (
+            vertexes[triangle[0]].position,
+            vertexes[triangle[1]].position,
+            vertexes[triangle[2]].position,
+        );
+        //not normalized, bigger faces weigh more
+        let normal = (b - a).cross(&(c - a));
+        for index in triangle {
+            vertexes[*index].normal += normal;
+        }
+    }
+    for vertex in vertexes.iter_mut() {
+        if vertex.normal.length2() > 0.0 {
+            vertex.normal = vertex.normal.normalize();
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Vertex {
     pub position: Vec3,
     pub normal: Vec3,
     pub texcoord: Vec2,
+    pub color: Vec4,       //linear rgba, multiplied with the base colour
+    pub joints: [u16; 4],  //indices into the joints of the skeleton
+    pub weights: [f32; 4], //all zero for a vertex that isn't skinned
 }
 impl Vertex {
     pub fn new(position: Vec3, normal: Vec3, texcoord: Vec2) -> Self {
@@ -62,16 +161,30 @@ impl Vertex {
             position,
             normal,
             texcoord,
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            joints: [0; 4],
+            weights: [0.0; 4],
         }
     }
+    pub fn with_color(mut self, color: Vec4) -> Self {
+        self.color = color;
+        self
+    }
+    pub fn with_skin(mut self, joints: [u16; 4], weights: [f32; 4]) -> Self {
+        self.joints = joints;
+        self.weights = weights;
+        self
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct Uniform {
-    world_position: Vec3,
-    world_normal: Vec3,
+    pub(crate) world_position: Vec3,
+    pub(crate) world_normal: Vec3,
     pub position: Vec3, //local position => clipping position => ndc position => screen(x,y) + ndc.z
-    texcoord: Vec2,
+    pub(crate) texcoord: Vec2,
+    pub color: Vec4,
+    pub lighting: Vec3, //gouraud only, the light reaching the vertex
 }
 impl Uniform {
     pub fn new(world_position: Vec3, world_normal: Vec3, position: Vec3, texcoord: Vec2) -> Self {
@@ -80,18 +193,37 @@ impl Uniform {
             world_normal,
             position,
             texcoord,
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            lighting: Vec3::zero(),
         }
     }
+    pub fn with_color(mut self, color: Vec4) -> Self {
+        self.color = color;
+        self
+    }
 }
 #[derive(Debug)]
 pub struct Triangle {
     pub a: Uniform,
     pub b: Uniform,
     pub c: Uniform,
+    pub shading: ShadingModel,
 }
 impl Triangle {
     pub fn new(a: Uniform, b: Uniform, c: Uniform) -> Self {
-        Self { a, b, c }
+        Self {
+            a,
+            b,
+            c,
+            shading: ShadingModel::default(),
+        }
+    }
+    //flat shading
+    pub fn use_face_normal(&mut self) {
+        let normal = self.get_world_normal();
+        self.a.world_normal = normal;
+        self.b.world_normal = normal;
+        self.c.world_normal = normal;
     }
 
     pub fn get_world_normal(&self) -> Vec3 {
@@ -123,8 +255,15 @@ impl Triangle {
         let texcoord = alpha * self.a.texcoord + beta * self.b.texcoord + gamma * self.c.texcoord;
         let depth =
             alpha * self.a.position.z + beta * self.b.position.z + gamma * self.c.position.z;
+        let color = alpha * self.a.color + beta * self.b.color + gamma * self.c.color;
+        let lighting = alpha * self.a.lighting + beta * self.b.lighting + gamma * self.c.lighting;
 
-        Fragment::new(world_position, world_normal, texcoord, screen_pos, depth)
+        Fragment {
+            color,
+            shading: self.shading,
+            lighting,
+            ..Fragment::new(world_position, world_normal, texcoord, screen_pos, depth)
+        }
     }
 }
 
@@ -134,6 +273,9 @@ pub struct Fragment {
     pub texcoord: Vec2,
     pub screen_pos: (u32, u32),
     pub depth: f32,
+    pub color: Vec4,
+    pub shading: ShadingModel,
+    pub lighting: Vec3,
 }
 
 impl Fragment {
@@ -150,6 +292,9 @@ impl Fragment {
             texcoord,
             screen_pos,
             depth,
+            color: Vec4::new(1.0, 1.0, 1.0, 1.0),
+            shading: ShadingModel::default(),
+            lighting: Vec3::zero(),
         }
     }
 }
@@ -0,0 +1,124 @@
+use crate::*;
+use std::borrow::Cow;
+
+//blend shape. the target shape itself instead of offsets from the base, so a weight of 1 gives it
+//back exactly. it can cover a part of the mesh, e.g. one of the meshes merged from a gltf file
+#[derive(Clone, Debug)]
+pub struct MorphTarget {
+    pub name: String,
+    pub start: usize, //vertex of the first position
+    pub positions: Vec<Vec3>,
+    pub normals: Vec<Vec3>, //empty keeps the normals of the base
+}
+impl MorphTarget {
+    pub fn new(name: &str, positions: Vec<Vec3>, normals: Vec<Vec3>) -> Self {
+        Self {
+            name: name.to_string(),
+            start: 0,
+            positions,
+            normals,
+        }
+    }
+    pub fn with_start(mut self, start: usize) -> Self {
+        self.start = start;
+        self
+    }
+    //`target` is the base mesh in another shape, with the same vertexes
+    pub fn from_shapes(name: &str, base: &[Vertex], target: &[Vertex]) -> Self {
+        assert_eq!(
+            base.len(),
+            target.len(),
+            "morph target {name} has another topology"
+        );
+        let positions = target.iter().map(|vertex| vertex.position).collect();
+        let normals = target.iter().map(|vertex| vertex.normal).collect();
+        Self::new(name, positions, normals)
+    }
+}
+
+//an obj sequence exported from the same mesh: the first file is the base, every other file becomes
+//a target named after the file
+pub fn load_morph_sequence(paths: &[&str]) -> (Vec<Vertex>, Vec<usize>, Vec<MorphTarget>) {
+    let (vertexes, indies) = load_model(paths[0]);
+    let targets = paths[1..]
+        .iter()
+        .map(|path| {
+            let name = std::path::Path::new(path)
+                .file_stem()
+                .unwrap()
+                .to_string_lossy();
+            let (target, target_indies) = load_model(path);
+            assert!(
+                target_indies == indies,
+                "morph target {name} has another topology"
+            );
+            MorphTarget::from_shapes(&name, &vertexes, &target)
+        })
+        .collect();
+    (vertexes, indies, targets)
+}
+
+impl Mesh {
+    pub fn with_morph_target(mut self, target: MorphTarget) -> Self {
+        assert!(target.start + target.positions.len() <= self.vertexes.len());
+        self.morph_targets.push(target);
+        self.morph_weights.push(0.0);
+        self
+    }
+    pub fn set_morph_weight(&mut self, target: usize, weight: f32) {
+        self.morph_weights[target] = weight;
+    }
+    pub fn find_morph_target(&self, name: &str) -> Option<usize> {
+        self.morph_targets
+            .iter()
+            .position(|target| target.name == name)
+    }
+
+    //(1 - Σ weight) * base + Σ weight * target for every vertex, the sums over the targets covering
+    //it. exact for weights of 0 and 1, the normals are normalized by the shader
+    pub fn get_morphed_vertexes(&self) -> Cow<'_, [Vertex]> {
+        let active: Vec<(&MorphTarget, f32)> = self
+            .morph_targets
+            .iter()
+            .zip(self.morph_weights.iter())
+            .filter(|(_, weight)| **weight != 0.0)
+            .map(|(target, weight)| (target, *weight))
+            .collect();
+        if active.is_empty() {
+            return Cow::Borrowed(&self.vertexes);
+        }
+
+        let mut base = vec![1.0; self.vertexes.len()];
+        for (target, weight) in active.iter() {
+            for base in base[target.start..][..target.positions.len()].iter_mut() {
+                *base -= weight;
+            }
+        }
+        let mut vertexes = self.vertexes.clone();
+        for (vertex, base) in vertexes.iter_mut().zip(base) {
+            if base != 1.0 {
+                vertex.position = base * vertex.position;
+                vertex.normal = base * vertex.normal;
+            }
+        }
+        for (target, weight) in active {
+            let range = target.start..target.start + target.positions.len();
+            for (vertex, position) in vertexes[range.clone()].iter_mut().zip(&target.positions) {
+                vertex.position += weight * *position;
+            }
+            let normals: Box<dyn Iterator<Item = Vec3>> = if target.normals.is_empty() {
+                Box::new(
+                    self.vertexes[range.clone()]
+                        .iter()
+                        .map(|vertex| vertex.normal),
+                )
+            } else {
+                Box::new(target.normals.iter().copied())
+            };
+            for (vertex, normal) in vertexes[range].iter_mut().zip(normals) {
+                vertex.normal += weight * normal;
+            }
+        }
+        Cow::Owned(vertexes)
+    }
+}
@@ -0,0 +1,210 @@
+use crate::*;
+use std::fs::read;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+impl Scalar {
+    fn parse(name: &str) -> Self {
+        match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => panic!("unknown ply type {name}"),
+        }
+    }
+    fn size(&self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+}
+
+struct Property {
+    name: String,
+    scalar: Scalar,
+    list: Option<Scalar>, //type of the length of a list property
+}
+
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+//the body after the header, values are read one by one as f64
+struct Body<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    cursor: usize,
+}
+impl Body<'_> {
+    fn next(&mut self, scalar: Scalar) -> f64 {
+        if self.format == Format::Ascii {
+            while self.bytes[self.cursor].is_ascii_whitespace() {
+                self.cursor += 1;
+            }
+            let start = self.cursor;
+            while self.cursor < self.bytes.len() && !self.bytes[self.cursor].is_ascii_whitespace() {
+                self.cursor += 1;
+            }
+            let token = std::str::from_utf8(&self.bytes[start..self.cursor]).unwrap();
+            return token.parse().unwrap();
+        }
+
+        let size = scalar.size();
+        let mut data = [0u8; 8];
+        data[..size].copy_from_slice(&self.bytes[self.cursor..self.cursor + size]);
+        self.cursor += size;
+        if self.format == Format::BinaryBigEndian {
+            data[..size].reverse();
+        }
+        match scalar {
+            Scalar::I8 => data[0] as i8 as f64,
+            Scalar::U8 => data[0] as f64,
+            Scalar::I16 => i16::from_le_bytes([data[0], data[1]]) as f64,
+            Scalar::U16 => u16::from_le_bytes([data[0], data[1]]) as f64,
+            Scalar::I32 => i32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+            Scalar::U32 => u32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+            Scalar::F32 => f32::from_le_bytes([data[0], data[1], data[2], data[3]]) as f64,
+            Scalar::F64 => f64::from_le_bytes(data),
+        }
+    }
+}
+
+//polygons are split into fans. without normals in the file the vertexes get the average of their faces
+pub fn load_ply(path: &str) -> (Vec<Vertex>, Vec<usize>) {
+    let bytes = read(path).unwrap();
+    let header_end = bytes
+        .windows(b"end_header".len())
+        .position(|window| window == b"end_header")
+        .unwrap();
+    let body_start = header_end
+        + bytes[header_end..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .unwrap()
+        + 1;
+    let header = std::str::from_utf8(&bytes[..header_end]).unwrap();
+
+    let mut format = Format::Ascii;
+    let mut elements: Vec<Element> = vec![];
+    for line in header.lines() {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        match args.as_slice() {
+            ["format", name, ..] => {
+                format = match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    _ => panic!("unknown ply format {name}"),
+                }
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().unwrap(),
+                properties: vec![],
+            }),
+            ["property", "list", length, scalar, name] => {
+                elements.last_mut().unwrap().properties.push(Property {
+                    name: name.to_string(),
+                    scalar: Scalar::parse(scalar),
+                    list: Some(Scalar::parse(length)),
+                })
+            }
+            ["property", scalar, name] => elements.last_mut().unwrap().properties.push(Property {
+                name: name.to_string(),
+                scalar: Scalar::parse(scalar),
+                list: None,
+            }),
+            _ => {} //ply, comment, obj_info
+        }
+    }
+
+    let mut body = Body {
+        format,
+        bytes: &bytes[body_start..],
+        cursor: 0,
+    };
+    let mut vertexes = vec![];
+    let mut indies = vec![];
+    let mut has_normal = false;
+    for element in elements.iter() {
+        for _ in 0..element.count {
+            let mut vertex = Vertex::new(Vec3::zero(), Vec3::zero(), Vec2::default());
+            for property in element.properties.iter() {
+                let Some(length) = property.list else {
+                    let value = body.next(property.scalar) as f32;
+                    //8 bit colours are srgb, float colours are taken as they are
+                    let color = |value: f32| match property.scalar {
+                        Scalar::U8 => srgb_to_linear(value / 255.0),
+                        _ => value,
+                    };
+                    let alpha = |value: f32| match property.scalar {
+                        Scalar::U8 => value / 255.0,
+                        _ => value,
+                    };
+                    match property.name.as_str() {
+                        "x" => vertex.position.x = value,
+                        "y" => vertex.position.y = value,
+                        "z" => vertex.position.z = value,
+                        "nx" => vertex.normal.x = value,
+                        "ny" => vertex.normal.y = value,
+                        "nz" => vertex.normal.z = value,
+                        "s" | "u" | "texture_u" => vertex.texcoord.x = value,
+                        "t" | "v" | "texture_v" => vertex.texcoord.y = value,
+                        "red" => vertex.color.x = color(value),
+                        "green" => vertex.color.y = color(value),
+                        "blue" => vertex.color.z = color(value),
+                        "alpha" => vertex.color.w = alpha(value),
+                        _ => {}
+                    }
+                    has_normal |= property.name == "nx";
+                    continue;
+                };
+                let count = body.next(length) as usize;
+                let polygon: Vec<usize> = (0..count)
+                    .map(|_| body.next(property.scalar) as usize)
+                    .collect();
+                if element.name == "face"
+                    && (property.name == "vertex_indices" || property.name == "vertex_index")
+                {
+                    for i in 1..polygon.len().saturating_sub(1) {
+                        indies.extend([polygon[0], polygon[i], polygon[i + 1]]);
+                    }
+                }
+            }
+            if element.name == "vertex" {
+                vertexes.push(vertex);
+            }
+        }
+    }
+
+    if !has_normal {
+        compute_normals(&mut vertexes, &indies);
+    }
+    (vertexes, indies)
+}
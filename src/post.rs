@@ -0,0 +1,636 @@
+use crate::*;
+use std::fs::read_to_string;
+
+const SSAO_NOISE_SIZE: u32 = 4; //rotations of the kernel repeat every 4 pixels, blurred away afterwards
+const SSAO_BIAS: f32 = 0.02;
+const FXAA_EDGE_THRESHOLD: f32 = 0.125;
+const FXAA_EDGE_THRESHOLD_MIN: f32 = 0.0312;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlurKernel {
+    Box,
+    Gaussian,
+}
+
+//3d colour lookup table, as written by most grading tools in the .cube format
+#[derive(Clone, Debug)]
+pub struct ColorLut {
+    size: usize,
+    domain_min: Vec3,
+    domain_max: Vec3,
+    data: Vec<Vec3>, //red changes fastest
+}
+impl ColorLut {
+    pub fn load(path: &str) -> Self {
+        Self::parse_cube(&read_to_string(path).unwrap())
+    }
+    pub fn parse_cube(source: &str) -> Self {
+        let mut size = 0;
+        let mut domain_min = Vec3::zero();
+        let mut domain_max = Vec3::new(1.0, 1.0, 1.0);
+        let mut data = vec![];
+        let vec3 = |values: &[&str]| {
+            Vec3::new(
+                values[0].parse().unwrap(),
+                values[1].parse().unwrap(),
+                values[2].parse().unwrap(),
+            )
+        };
+        for line in source.lines().map(str::trim) {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            match values.first() {
+                None => {}
+                Some(value) if value.parse::<f32>().is_ok() => data.push(vec3(&values)),
+                Some(&"LUT_3D_SIZE") => size = values[1].parse().unwrap(),
+                Some(&"DOMAIN_MIN") => domain_min = vec3(&values[1..]),
+                Some(&"DOMAIN_MAX") => domain_max = vec3(&values[1..]),
+                //the same range for every channel
+                Some(&"LUT_3D_INPUT_RANGE") => {
+                    let value = |i: usize| values[i].parse::<f32>().unwrap();
+                    domain_min = Vec3::new(value(1), value(1), value(1));
+                    domain_max = Vec3::new(value(2), value(2), value(2));
+                }
+                //comments, TITLE and keywords of other tools
+                Some(_) => {}
+            }
+        }
+        assert!(size >= 2, "only 3d luts are supported");
+        assert_eq!(
+            data.len(),
+            size * size * size,
+            "wrong number of lut entries"
+        );
+        Self {
+            size,
+            domain_min,
+            domain_max,
+            data,
+        }
+    }
+
+    fn get(&self, r: usize, g: usize, b: usize) -> Vec3 {
+        self.data[(b * self.size + g) * self.size + r]
+    }
+    //trilinear
+    pub fn sample(&self, color: Vec3) -> Vec3 {
+        let max = (self.size - 1) as f32;
+        let scale = |c: f32, min: f32, max_domain: f32| {
+            ((c - min) / (max_domain - min)).clamp(0.0, 1.0) * max
+        };
+        let r = scale(color.x, self.domain_min.x, self.domain_max.x);
+        let g = scale(color.y, self.domain_min.y, self.domain_max.y);
+        let b = scale(color.z, self.domain_min.z, self.domain_max.z);
+        let (r0, g0, b0) = (r.floor() as usize, g.floor() as usize, b.floor() as usize);
+        let (r1, g1, b1) = (
+            (r0 + 1).min(self.size - 1),
+            (g0 + 1).min(self.size - 1),
+            (b0 + 1).min(self.size - 1),
+        );
+        let (tr, tg, tb) = (r - r0 as f32, g - g0 as f32, b - b0 as f32);
+
+        let lerp = |a: Vec3, b: Vec3, t: f32| (1.0 - t) * a + t * b;
+        let c00 = lerp(self.get(r0, g0, b0), self.get(r1, g0, b0), tr);
+        let c10 = lerp(self.get(r0, g1, b0), self.get(r1, g1, b0), tr);
+        let c01 = lerp(self.get(r0, g0, b1), self.get(r1, g0, b1), tr);
+        let c11 = lerp(self.get(r0, g1, b1), self.get(r1, g1, b1), tr);
+        lerp(lerp(c00, c10, tg), lerp(c01, c11, tg), tb)
+    }
+}
+
+//one pass of the post stack, passes run in the order they were added
+#[derive(Clone, Debug)]
+pub enum PostEffect {
+    Fxaa,
+    Blur {
+        kernel: BlurKernel,
+        radius: u32,
+    },
+    Bloom {
+        threshold: f32, //linear luminance where blooming starts
+        intensity: f32,
+        radius: u32,
+    },
+    Ssao {
+        radius: f32, //world units
+        intensity: f32,
+        samples: u32,
+    },
+    Vignette {
+        intensity: f32,
+        radius: f32, //0 at the centre, 1 at the corners
+    },
+    ColorGrading(ColorLut), //looked up with srgb values, so put it after the hdr passes
+    Fog {
+        color: Vec3,
+        density: f32, //1 - e^(-density * distance)
+    },
+    //lines where the depth or the normal of neighbouring pixels jumps, i.e. silhouettes and creases
+    Outline {
+        color: Vec3,
+        depth_threshold: f32,  //relative to the distance, 0.05 = 5%
+        normal_threshold: f32, //1 - cos of the angle between the normals
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PostStack {
+    pub effects: Vec<PostEffect>,
+}
+impl PostStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, effect: PostEffect) {
+        self.effects.push(effect)
+    }
+    pub fn with(mut self, effect: PostEffect) -> Self {
+        self.add(effect);
+        self
+    }
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+}
+
+//linear colour and the depth of every pixel, what the passes work on
+struct PostImage<'a> {
+    width: u32,
+    height: u32,
+    color: Vec<Vec3>,
+    depth: &'a DepthBuffer,
+    projection: Matrix4,
+    inv_projection: Option<Matrix4>, //None for a degenerated camera, nothing is reconstructed
+}
+impl PostImage<'_> {
+    fn get(&self, x: i32, y: i32) -> Vec3 {
+        let x = x.clamp(0, self.width as i32 - 1) as u32;
+        let y = y.clamp(0, self.height as i32 - 1) as u32;
+        self.color[(y * self.width + x) as usize]
+    }
+    //bilinear, in pixels
+    fn sample(&self, x: f32, y: f32) -> Vec3 {
+        let (x, y) = (x - 0.5, y - 0.5);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (tx, ty) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i32, y0 as i32);
+        let top = (1.0 - tx) * self.get(x0, y0) + tx * self.get(x0 + 1, y0);
+        let bottom = (1.0 - tx) * self.get(x0, y0 + 1) + tx * self.get(x0 + 1, y0 + 1);
+        (1.0 - ty) * top + ty * bottom
+    }
+
+    //None where nothing was drawn
+    fn view_position(&self, x: i32, y: i32) -> Option<Vec3> {
+        if x < 0 || y < 0 {
+            return None;
+        }
+        let depth = self.depth.get_depth(x as u32, y as u32)?;
+        if depth <= -1.0 {
+            return None;
+        }
+        let ndc_x = (x as f32 + 0.5) / self.width as f32 * 2.0 - 1.0;
+        let ndc_y = 1.0 - (y as f32 + 0.5) / self.height as f32 * 2.0;
+        Some(
+            self.inv_projection?
+                .mul(Vec4::new(ndc_x, ndc_y, depth, 1.0))
+                .perspective_divide(),
+        )
+    }
+    //normal from the neighbours, the smaller difference avoids bleeding over silhouettes
+    fn view_normal(&self, x: i32, y: i32) -> Option<Vec3> {
+        let position = self.view_position(x, y)?;
+        let neighbour = |dx: i32, dy: i32| {
+            let a = self.view_position(x + dx, y + dy);
+            let b = self
+                .view_position(x - dx, y - dy)
+                .map(|b| 2.0 * position - b);
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    if (a.z - position.z).abs() < (b.z - position.z).abs() {
+                        a
+                    } else {
+                        b
+                    }
+                }
+                (Some(p), None) | (None, Some(p)) => p,
+                (None, None) => position,
+            }
+        };
+        let ddx = neighbour(1, 0) - position;
+        let ddy = neighbour(0, 1) - position;
+        let normal = ddy.cross(&ddx);
+        if normal.length2() < 1e-12 {
+            return None;
+        }
+        Some(normal.normalize())
+    }
+    fn to_screen(&self, view_position: Vec3) -> (i32, i32) {
+        let ndc = self
+            .projection
+            .mul(view_position.upgrade())
+            .perspective_divide();
+        (
+            ((ndc.x + 1.0) * 0.5 * self.width as f32).floor() as i32,
+            ((1.0 - ndc.y) * 0.5 * self.height as f32).floor() as i32,
+        )
+    }
+}
+
+fn luma(color: Vec3) -> f32 {
+    color.dot(&Vec3::new(0.2126, 0.7152, 0.0722))
+}
+
+fn blur_weights(kernel: BlurKernel, radius: u32) -> Vec<f32> {
+    let sigma = (radius as f32 / 2.0).max(0.5);
+    let weights: Vec<f32> = (-(radius as i32)..=radius as i32)
+        .map(|i| match kernel {
+            BlurKernel::Box => 1.0,
+            BlurKernel::Gaussian => (-(i * i) as f32 / (2.0 * sigma * sigma)).exp(),
+        })
+        .collect();
+    let sum: f32 = weights.iter().sum();
+    weights.iter().map(|w| w / sum).collect()
+}
+
+//separable, horizontal then vertical
+fn blur(color: &[Vec3], width: u32, height: u32, kernel: BlurKernel, radius: u32) -> Vec<Vec3> {
+    let weights = blur_weights(kernel, radius);
+    let radius = radius as i32;
+    let pass = |source: &[Vec3], dx: i32, dy: i32| {
+        let mut target = vec![Vec3::zero(); source.len()];
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                let mut sum = Vec3::zero();
+                for (i, weight) in (-radius..=radius).zip(&weights) {
+                    let sx = (x + i * dx).clamp(0, width as i32 - 1);
+                    let sy = (y + i * dy).clamp(0, height as i32 - 1);
+                    sum += *weight * source[(sy * width as i32 + sx) as usize];
+                }
+                target[(y * width as i32 + x) as usize] = sum;
+            }
+        }
+        target
+    };
+    pass(&pass(color, 1, 0), 0, 1)
+}
+
+fn fxaa(image: &PostImage) -> Vec<Vec3> {
+    //perceptual luma, the image is linear
+    let luma_at = |x: i32, y: i32| luma(image.get(x, y)).max(0.0).sqrt();
+    let mut target = image.color.clone();
+    for y in 0..image.height as i32 {
+        for x in 0..image.width as i32 {
+            let (m, n, s, w, e) = (
+                luma_at(x, y),
+                luma_at(x, y - 1),
+                luma_at(x, y + 1),
+                luma_at(x - 1, y),
+                luma_at(x + 1, y),
+            );
+            let range_min = m.min(n).min(s).min(w).min(e);
+            let range_max = m.max(n).max(s).max(w).max(e);
+            let range = range_max - range_min;
+            if range < FXAA_EDGE_THRESHOLD_MIN.max(range_max * FXAA_EDGE_THRESHOLD) {
+                continue;
+            }
+
+            let (nw, ne, sw, se) = (
+                luma_at(x - 1, y - 1),
+                luma_at(x + 1, y - 1),
+                luma_at(x - 1, y + 1),
+                luma_at(x + 1, y + 1),
+            );
+            //blend across the edge, along its gradient
+            let horizontal = (nw + ne - 2.0 * n).abs()
+                + 2.0 * (w + e - 2.0 * m).abs()
+                + (sw + se - 2.0 * s).abs();
+            let vertical = (nw + sw - 2.0 * w).abs()
+                + 2.0 * (n + s - 2.0 * m).abs()
+                + (ne + se - 2.0 * e).abs();
+            let is_horizontal = horizontal >= vertical;
+            let (positive, negative) = if is_horizontal { (s, n) } else { (e, w) };
+            let step = if (positive - m).abs() >= (negative - m).abs() {
+                1.0
+            } else {
+                -1.0
+            };
+
+            let average = (2.0 * (n + s + w + e) + nw + ne + sw + se) / 12.0;
+            let subpixel = ((average - m).abs() / range).clamp(0.0, 1.0);
+            let blend = (subpixel * subpixel * (3.0 - 2.0 * subpixel)).powi(2) * 0.75;
+
+            let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+            let color = if is_horizontal {
+                image.sample(px, py + step * blend)
+            } else {
+                image.sample(px + step * blend, py)
+            };
+            target[(y as u32 * image.width + x as u32) as usize] = color;
+        }
+    }
+    target
+}
+
+fn bloom(image: &PostImage, threshold: f32, intensity: f32, radius: u32) -> Vec<Vec3> {
+    let bright: Vec<Vec3> = image
+        .color
+        .iter()
+        .map(|color| {
+            let l = luma(*color);
+            if l > threshold {
+                ((l - threshold) / l) * *color
+            } else {
+                Vec3::zero()
+            }
+        })
+        .collect();
+    let blurred = blur(
+        &bright,
+        image.width,
+        image.height,
+        BlurKernel::Gaussian,
+        radius,
+    );
+    image
+        .color
+        .iter()
+        .zip(blurred)
+        .map(|(color, glow)| *color + intensity * glow)
+        .collect()
+}
+
+//cheap integer hash -> [0,1)
+pub(crate) fn hash(mut h: u32) -> f32 {
+    h = (h ^ 61) ^ (h >> 16);
+    h = h.wrapping_mul(9);
+    h ^= h >> 4;
+    h = h.wrapping_mul(0x27d4_eb2d);
+    h ^= h >> 15;
+    (h >> 8) as f32 / (1 << 24) as f32
+}
+
+fn ssao(image: &PostImage, radius: f32, intensity: f32, samples: u32) -> Vec<Vec3> {
+    //hemisphere around +z, denser close to the centre
+    let kernel: Vec<Vec3> = (0..samples)
+        .map(|i| {
+            let direction = Vec3::new(
+                hash(3 * i) * 2.0 - 1.0,
+                hash(3 * i + 1) * 2.0 - 1.0,
+                hash(3 * i + 2),
+            )
+            .normalize();
+            let scale = (i as f32 + 1.0) / samples as f32;
+            (0.1 + 0.9 * scale * scale) * direction
+        })
+        .collect();
+
+    let (width, height) = (image.width as i32, image.height as i32);
+    let mut occlusion = vec![1.0f32; image.color.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let Some(position) = image.view_position(x, y) else {
+                continue;
+            };
+            let Some(normal) = image.view_normal(x, y) else {
+                continue;
+            };
+
+            let noise = (y as u32 % SSAO_NOISE_SIZE) * SSAO_NOISE_SIZE + x as u32 % SSAO_NOISE_SIZE;
+            let angle = hash(noise + 1000) * 2.0 * std::f32::consts::PI;
+            let random = Vec3::new(angle.cos(), angle.sin(), 0.0);
+            let tangent = (random - random.dot(&normal) * normal).normalize();
+            let bitangent = normal.cross(&tangent);
+
+            let mut occluded = 0.0;
+            for sample in &kernel {
+                let offset = sample.x * tangent + sample.y * bitangent + sample.z * normal;
+                let sample_position = position + radius * offset;
+                let (sx, sy) = image.to_screen(sample_position);
+                let Some(scene) = image.view_position(sx, sy) else {
+                    continue;
+                };
+                //camera looks at -z, a bigger z is closer to the camera
+                if scene.z >= sample_position.z + SSAO_BIAS * radius {
+                    let range = (radius / (position.z - scene.z).abs()).clamp(0.0, 1.0);
+                    occluded += range * range * (3.0 - 2.0 * range);
+                }
+            }
+            occlusion[(y * width + x) as usize] = 1.0 - occluded / samples as f32;
+        }
+    }
+
+    //box blur over the noise tile
+    let half = SSAO_NOISE_SIZE as i32 / 2;
+    image
+        .color
+        .iter()
+        .enumerate()
+        .map(|(i, color)| {
+            let (x, y) = (i as i32 % width, i as i32 / width);
+            let mut sum = 0.0;
+            let mut count = 0.0;
+            for dy in -half..half {
+                for dx in -half..half {
+                    let (sx, sy) = (x + dx, y + dy);
+                    if sx >= 0 && sy >= 0 && sx < width && sy < height {
+                        sum += occlusion[(sy * width + sx) as usize];
+                        count += 1.0;
+                    }
+                }
+            }
+            (sum / count).powf(intensity) * *color
+        })
+        .collect()
+}
+
+fn vignette(image: &PostImage, intensity: f32, radius: f32) -> Vec<Vec3> {
+    let (cx, cy) = (image.width as f32 * 0.5, image.height as f32 * 0.5);
+    let corner = (cx * cx + cy * cy).sqrt();
+    image
+        .color
+        .iter()
+        .enumerate()
+        .map(|(i, color)| {
+            let x = (i as u32 % image.width) as f32 + 0.5 - cx;
+            let y = (i as u32 / image.width) as f32 + 0.5 - cy;
+            let distance = (x * x + y * y).sqrt() / corner;
+            let t = ((distance - radius) / (1.0 - radius).max(1e-4)).clamp(0.0, 1.0);
+            (1.0 - intensity * t * t * (3.0 - 2.0 * t)) * *color
+        })
+        .collect()
+}
+
+fn color_grading(image: &PostImage, lut: &ColorLut) -> Vec<Vec3> {
+    image
+        .color
+        .iter()
+        .map(|color| {
+            let srgb = Vec3::new(
+                linear_to_srgb(color.x.clamp(0.0, 1.0)),
+                linear_to_srgb(color.y.clamp(0.0, 1.0)),
+                linear_to_srgb(color.z.clamp(0.0, 1.0)),
+            );
+            let graded = lut.sample(srgb);
+            Vec3::new(
+                srgb_to_linear(graded.x),
+                srgb_to_linear(graded.y),
+                srgb_to_linear(graded.z),
+            )
+        })
+        .collect()
+}
+
+fn fog(image: &PostImage, fog_color: Vec3, density: f32) -> Vec<Vec3> {
+    image
+        .color
+        .iter()
+        .enumerate()
+        .map(|(i, color)| {
+            let (x, y) = (i as i32 % image.width as i32, i as i32 / image.width as i32);
+            match image.view_position(x, y) {
+                Some(position) => {
+                    let distance = position.length2().sqrt();
+                    let f = 1.0 - (-density * distance).exp();
+                    (1.0 - f) * *color + f * fog_color
+                }
+                None => *color, //keep the background
+            }
+        })
+        .collect()
+}
+
+fn outline(
+    image: &PostImage,
+    outline_color: Vec3,
+    depth_threshold: f32,
+    normal_threshold: f32,
+) -> Vec<Vec3> {
+    let (width, height) = (image.width as i32, image.height as i32);
+    let mut positions = Vec::with_capacity(image.color.len());
+    let mut normals = Vec::with_capacity(image.color.len());
+    for y in 0..height {
+        for x in 0..width {
+            positions.push(image.view_position(x, y));
+            normals.push(image.view_normal(x, y));
+        }
+    }
+    let at = |x: i32, y: i32| {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            None
+        } else {
+            Some((y * width + x) as usize)
+        }
+    };
+
+    image
+        .color
+        .iter()
+        .enumerate()
+        .map(|(i, color)| {
+            let (x, y) = (i as i32 % width, i as i32 / width);
+            let Some(position) = positions[i] else {
+                return *color;
+            };
+            //depth edges are drawn on the closer side, so the line hugs the object in front
+            let depth_edge = [(1, 0), (-1, 0), (0, 1), (0, -1)].iter().any(|(dx, dy)| {
+                match at(x + dx, y + dy).map(|j| positions[j]) {
+                    Some(Some(other)) => position.z - other.z > depth_threshold * position.z.abs(),
+                    Some(None) => true, //next to the background
+                    None => false,
+                }
+            });
+            //creases only look right and down, one pixel wide lines
+            let normal_edge = normals[i].is_some_and(|normal| {
+                [(1, 0), (0, 1)].iter().any(|(dx, dy)| {
+                    at(x + dx, y + dy)
+                        .and_then(|j| normals[j])
+                        .is_some_and(|other| 1.0 - normal.dot(&other) > normal_threshold)
+                })
+            });
+            if depth_edge || normal_edge {
+                outline_color
+            } else {
+                *color
+            }
+        })
+        .collect()
+}
+
+impl Render {
+    pub fn set_post_stack(&mut self, post_stack: PostStack) {
+        self.post_stack = post_stack
+    }
+    pub fn get_post_stack(&mut self) -> &mut PostStack {
+        &mut self.post_stack
+    }
+
+    //run the post stack on the linear colour, the frame buffer is decoded first without hdr
+    pub(crate) fn post_process(&mut self) {
+        if self.post_stack.is_empty() {
+            return;
+        }
+        let color = match &self.hdr_buffer {
+            Some(hdr_buffer) => (0..self.height)
+                .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+                .map(|(x, y)| hdr_buffer.get_color(x, y).unwrap())
+                .collect(),
+            None => self
+                .frame_buffer
+                .flatten()
+                .chunks(3)
+                .map(|pixel| self.output_transform.decode([pixel[0], pixel[1], pixel[2]]))
+                .collect(),
+        };
+        let projection = self.camera.get_projection_matrix();
+        let mut image = PostImage {
+            width: self.width,
+            height: self.height,
+            color,
+            depth: &self.depth_buffer,
+            projection,
+            inv_projection: projection.inv(),
+        };
+
+        for effect in &self.post_stack.effects {
+            image.color = match effect {
+                PostEffect::Fxaa => fxaa(&image),
+                PostEffect::Blur { kernel, radius } => {
+                    blur(&image.color, image.width, image.height, *kernel, *radius)
+                }
+                PostEffect::Bloom {
+                    threshold,
+                    intensity,
+                    radius,
+                } => bloom(&image, *threshold, *intensity, *radius),
+                PostEffect::Ssao {
+                    radius,
+                    intensity,
+                    samples,
+                } => ssao(&image, *radius, *intensity, *samples),
+                PostEffect::Vignette { intensity, radius } => vignette(&image, *intensity, *radius),
+                PostEffect::ColorGrading(lut) => color_grading(&image, lut),
+                PostEffect::Fog { color, density } => fog(&image, *color, *density),
+                PostEffect::Outline {
+                    color,
+                    depth_threshold,
+                    normal_threshold,
+                } => outline(&image, *color, *depth_threshold, *normal_threshold),
+            };
+        }
+
+        let color = image.color;
+        for (i, color) in color.into_iter().enumerate() {
+            let (x, y) = (i as u32 % self.width, i as u32 / self.width);
+            match &mut self.hdr_buffer {
+                Some(hdr_buffer) => hdr_buffer.set_color(x, y, color),
+                //only undo the decoding, the colours were tone mapped already
+                None => self.frame_buffer.draw_pixel(
+                    (x, y),
+                    OutputTransform::default()
+                        .with_srgb(self.output_transform.srgb)
+                        .with_dither(self.output_transform.dither)
+                        .encode(color, x, y),
+                ),
+            }
+        }
+    }
+}
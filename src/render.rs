@@ -1,12 +1,22 @@
 use crate::*;
 
 pub struct Render {
-    width: u32,
-    height: u32,
-    frame_buffer: FrameBuffer,
-    depth_buffer: DepthBuffer,
-    camera: Camera,
-    shader: Shader,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) frame_buffer: FrameBuffer,
+    pub(crate) depth_buffer: DepthBuffer,
+    pub(crate) camera: Camera,
+    pub(crate) shader: Shader,
+    render_mode: RenderMode,
+    pub(crate) debug_view: DebugView,
+    pub(crate) overdraw_buffer: Buffer<u32>,
+    pub(crate) frame_stats: RenderStats,
+    pub(crate) background: Background,
+    pub(crate) hdr_buffer: Option<HdrFrameBuffer>,
+    pub(crate) output_transform: OutputTransform,
+    pub(crate) post_stack: PostStack,
+    pub(crate) g_buffer: Option<GBuffer>, //created by the first deferred draw
+    pub(crate) frustum_culling: bool,
 }
 impl Render {
     pub fn new(width: u32, height: u32, camera: Camera, shader: Shader) -> Self {
@@ -17,11 +27,94 @@ impl Render {
             depth_buffer: DepthBuffer::new(width, height, -1.0),
             camera,
             shader,
+            render_mode: RenderMode::Shaded,
+            debug_view: DebugView::None,
+            overdraw_buffer: Buffer::new_with_capacity(width, height, 0),
+            frame_stats: RenderStats::default(),
+            background: Background::default(),
+            hdr_buffer: None,
+            output_transform: OutputTransform::default(),
+            post_stack: PostStack::new(),
+            g_buffer: None,
+            frustum_culling: true,
+        }
+    }
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode
+    }
+    pub fn get_render_mode(&self) -> RenderMode {
+        self.render_mode
+    }
+    //skip meshes and scene nodes whose bounds are outside of the view
+    pub fn set_frustum_culling(&mut self, frustum_culling: bool) {
+        self.frustum_culling = frustum_culling
+    }
+    pub fn get_frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
+
+    //with hdr on, fragments are accumulated in linear f32 and only `resolve` writes the 8 bit frame
+    pub fn set_hdr(&mut self, hdr: bool) {
+        self.hdr_buffer = match hdr {
+            true => Some(HdrFrameBuffer::new(self.width, self.height, Vec3::zero())),
+            false => None,
+        }
+    }
+    pub fn is_hdr(&self) -> bool {
+        self.hdr_buffer.is_some()
+    }
+    pub fn get_hdr_frame(&self) -> Option<&HdrFrameBuffer> {
+        self.hdr_buffer.as_ref()
+    }
+    pub fn set_output_transform(&mut self, output_transform: OutputTransform) {
+        self.output_transform = output_transform
+    }
+    pub fn get_output_transform(&self) -> OutputTransform {
+        self.output_transform
+    }
+
+    //run the post stack and tone map the hdr buffer into the frame buffer,
+    //2d overlays like text go after it
+    pub fn resolve(&mut self) {
+        let stage = stage!("resolve");
+        self.post_process();
+        if let Some(hdr_buffer) = &self.hdr_buffer {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    let color = hdr_buffer.get_color(x, y).unwrap();
+                    self.frame_buffer
+                        .draw_pixel((x, y), self.output_transform.encode(color, x, y));
+                }
+            }
+        }
+        self.frame_stats.resolve_time += stage.end();
+    }
+
+    //linear colour of the scene
+    pub(crate) fn write_color(&mut self, x: u32, y: u32, color: Vec3) {
+        match &mut self.hdr_buffer {
+            Some(hdr_buffer) => hdr_buffer.set_color(x, y, color),
+            None => {
+                let color = self.output_transform.encode(color, x, y);
+                self.frame_buffer.draw_pixel((x, y), color)
+            }
+        }
+    }
+    //colour picked for the screen, e.g. of debug lines, kept as it is without hdr
+    pub(crate) fn write_display_color(&mut self, x: u32, y: u32, color: Color) {
+        match &mut self.hdr_buffer {
+            Some(hdr_buffer) => hdr_buffer.set_color(x, y, decode_srgb(color)),
+            None => self.frame_buffer.draw_pixel((x, y), color),
         }
     }
     pub fn reset(&mut self) {
-        self.frame_buffer.reset([0; 3]);
+        self.draw_background();
         self.depth_buffer.reset(-1.0);
+        self.overdraw_buffer.reset(0);
+        if let Some(g_buffer) = &mut self.g_buffer {
+            g_buffer.reset();
+        }
+        self.frame_stats = RenderStats::default();
     }
     fn digital_differential_analyzer_draw_line(
         &mut self,
@@ -195,57 +288,156 @@ impl Render {
         fragments
     }
 
-    pub fn draw(&mut self, mesh: &Mesh, light: &PointLight, model_mat: Matrix4) {
+    //vertex shading and primitive assembly, `lights` for gouraud shading
+    pub(crate) fn assemble(
+        &mut self,
+        mesh: &Mesh,
+        model_mat: Matrix4,
+        lights: Option<&LightSet>,
+        stats: &mut RenderStats,
+    ) -> Vec<Triangle> {
+        self.assemble_posed(mesh, &mesh.get_posed_vertexes(), model_mat, lights, stats)
+    }
+    pub(crate) fn assemble_posed(
+        &mut self,
+        mesh: &Mesh,
+        vertexes: &[Vertex],
+        model_mat: Matrix4,
+        lights: Option<&LightSet>,
+        stats: &mut RenderStats,
+    ) -> Vec<Triangle> {
         //Vertex Shader
+        let stage = stage!("vertex_shading");
         let mvp = self.camera.get_projection_matrix() * self.camera.get_view_matrix() * model_mat;
-        let uniforms: Vec<Uniform> = mesh
-            .vertexes
+        //the planes of the model view projection bound the mesh in its own space
+        if self.frustum_culling
+            && Aabb::from_vertexes(vertexes)
+                .is_some_and(|aabb| !FrustumPlanes::from_matrix(&mvp).intersects_aabb(&aabb))
+        {
+            stats.meshes_culled = 1;
+            stats.vertex_shading_time = stage.end();
+            return vec![];
+        }
+        let mut uniforms: Vec<Uniform> = vertexes
             .iter()
             .map(|vertex| self.shader.run_vertex_shader(vertex, &mvp, &model_mat))
             .collect();
+        if let (ShadingModel::Gouraud, Some(lights)) = (mesh.shading, lights) {
+            let camera_position = self.camera.get_position();
+            for uniform in uniforms.iter_mut() {
+                uniform.lighting = shade_vertex(uniform, &mesh.material, lights, &camera_position);
+            }
+        }
+        stats.vertices_shaded = uniforms.len();
+        stats.vertex_shading_time = stage.end();
 
         //Primitive Assembly
+        let stage = stage!("assembly");
         let n_face = mesh.indies.len() / 3;
         let mut triangles = Vec::with_capacity(n_face);
         for i in 0..n_face {
-            let triangle = Triangle::new(
+            let mut triangle = Triangle::new(
                 uniforms[mesh.indies[3 * i]],
                 uniforms[mesh.indies[3 * i + 1]],
                 uniforms[mesh.indies[3 * i + 2]],
             );
+            triangle.shading = mesh.shading;
+            if mesh.shading == ShadingModel::Flat {
+                triangle.use_face_normal();
+            }
             triangles.push(triangle)
         }
+        stats.triangles_assembled = triangles.len();
+        stats.assembly_time = stage.end();
+        triangles
+    }
 
+    //culling, screen mapping and rasterization, returns the triangles which are left
+    pub(crate) fn rasterize(
+        &mut self,
+        mut triangles: Vec<Triangle>,
+        stats: &mut RenderStats,
+    ) -> (Vec<Triangle>, Vec<Fragment>) {
+        let stage = stage!("assembly");
         //back face culling
         let camera_dir = self.camera.get_dir();
         triangles.retain(|triangle| triangle.get_world_normal().dot(&camera_dir) < 0.0);
+        stats.triangles_back_face_culled = stats.triangles_assembled - triangles.len();
 
         //near & far plane culling
         triangles.retain(|triangle| !triangle.ndc_culling_test());
+        stats.triangles_ndc_culled =
+            stats.triangles_assembled - stats.triangles_back_face_culled - triangles.len();
 
         //TODO:clipping
 
         //screen mapping
-        let width = self.width as f32;
-        let height = self.height as f32;
         triangles.iter_mut().for_each(|triangle| {
-            //Screen origin is Top left corner
-            let view_prot_transform = |ndc: &mut Vec3| {
-                ndc.x = (ndc.x + 1.0) * 0.5 * width as f32;
-                ndc.y = (-ndc.y + 1.0) * 0.5 * height as f32;
-            };
-            view_prot_transform(&mut triangle.a.position);
-            view_prot_transform(&mut triangle.b.position);
-            view_prot_transform(&mut triangle.c.position);
+            self.view_port_transform(&mut triangle.a.position);
+            self.view_port_transform(&mut triangle.b.position);
+            self.view_port_transform(&mut triangle.c.position);
         });
+        stats.assembly_time += stage.end();
 
         //Rasterization
+        let stage = stage!("raster");
         let fragments: Vec<Fragment> = triangles
             .iter()
             .flat_map(|triangle| self.raster_triangle(triangle))
             .collect();
+        stats.triangles_rasterized = triangles.len();
+        stats.fragments_generated = fragments.len();
+        stats.raster_time = stage.end();
+        (triangles, fragments)
+    }
+
+    pub fn draw(&mut self, mesh: &Mesh, lights: &LightSet, model_mat: Matrix4) -> RenderStats {
+        self.draw_posed(mesh, &mesh.get_posed_vertexes(), lights, model_mat)
+    }
+    //`vertexes` are the posed vertexes of `mesh`, for meshes drawn more than once in a frame
+    pub(crate) fn draw_posed(
+        &mut self,
+        mesh: &Mesh,
+        vertexes: &[Vertex],
+        lights: &LightSet,
+        model_mat: Matrix4,
+    ) -> RenderStats {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!("draw", vertices = mesh.vertexes.len()).entered();
+        let mut stats = RenderStats::default();
+
+        let triangles = self.assemble_posed(mesh, vertexes, model_mat, Some(lights), &mut stats);
+        if self.debug_view != DebugView::None {
+            stats += self.draw_debug_view(triangles);
+            self.frame_stats += stats;
+            return stats;
+        }
+        let (triangles, fragments) = self.rasterize(triangles, &mut stats);
+
+        if self.render_mode == RenderMode::Wireframe {
+            //depth only pass, so the edges are hidden by the faces in front of them
+            let stage = stage!("merge");
+            fragments.iter().for_each(|fragment| {
+                let (x, y) = fragment.screen_pos;
+                if self
+                    .depth_buffer
+                    .get_depth(x, y)
+                    .is_some_and(|depth| fragment.depth > depth)
+                {
+                    self.depth_buffer.set_depth(x, y, fragment.depth);
+                    stats.depth_test_passed += 1;
+                } else {
+                    stats.depth_test_failed += 1;
+                }
+            });
+            self.draw_triangle_edges(&triangles, WIREFRAME_COLOR);
+            stats.merge_time = stage.end();
+            self.frame_stats += stats;
+            return stats;
+        }
 
         //Fragment Shader
+        let stage = stage!("fragment_shading");
         let camera_position = self.camera.get_position();
         let shaded_fragments: Vec<ShadedFragment> = fragments
             .iter()
@@ -253,24 +445,80 @@ impl Render {
                 self.shader.run_fragment_shader(
                     fragment,
                     &mesh.material,
-                    light,
+                    lights,
                     &camera_position,
                     &model_mat,
                 )
             })
             .collect();
+        stats.fragment_shading_time = stage.end();
 
         //Output merge
         //depth test
+        let stage = stage!("merge");
         shaded_fragments.iter().for_each(|shaded_fragment| {
             if self.depth_buffer.depth_test(shaded_fragment) {
                 self.depth_buffer.depth_write(shaded_fragment);
 
-                self.frame_buffer
-                    .draw_pixel(shaded_fragment.screen_pos, shaded_fragment.color)
+                let (x, y) = shaded_fragment.screen_pos;
+                self.write_color(x, y, shaded_fragment.color);
+                stats.depth_test_passed += 1;
+            } else {
+                stats.depth_test_failed += 1;
             }
         });
+
+        if self.render_mode == RenderMode::ShadedWireframe {
+            self.draw_triangle_edges(&triangles, WIREFRAME_COLOR);
+        }
+        stats.merge_time = stage.end();
+
+        self.frame_stats += stats;
+        stats
+    }
+    //statistics of every draw since the last reset
+    pub fn get_frame_stats(&self) -> RenderStats {
+        self.frame_stats
     }
+    //Screen origin is Top left corner
+    pub(crate) fn view_port_transform(&self, ndc: &mut Vec3) {
+        ndc.x = (ndc.x + 1.0) * 0.5 * self.width as f32;
+        ndc.y = (-ndc.y + 1.0) * 0.5 * self.height as f32;
+    }
+
+    pub(crate) fn get_view_projection(&mut self) -> Matrix4 {
+        self.camera.get_projection_matrix() * self.camera.get_view_matrix()
+    }
+
+    //world position -> screen position, None if it is behind the camera
+    pub fn project(&mut self, world_position: Vec3) -> Option<Vec2> {
+        let view_projection = self.get_view_projection();
+        let clip = view_projection.mul(world_position.upgrade());
+        //camera looks at -z, so w = z_view is negative in front of the camera
+        if clip.w >= 0.0 {
+            return None;
+        }
+        let mut ndc = clip.perspective_divide();
+        self.view_port_transform(&mut ndc);
+        Some(ndc.downgrade())
+    }
+
+    pub fn draw_text(&mut self, pos: Vec2, text: &str, color: Color, scale: u32) {
+        self.frame_buffer.draw_text(pos, text, color, scale)
+    }
+    pub fn draw_text_with(
+        &mut self,
+        font: &Font,
+        pos: Vec2,
+        text: &str,
+        color: Color,
+        scale: u32,
+        align: TextAlign,
+    ) {
+        self.frame_buffer
+            .draw_text_with(font, pos, text, color, scale, align)
+    }
+
     pub fn get_frame(&self) -> Vec<u8> {
         self.frame_buffer.flatten()
     }
@@ -279,6 +527,16 @@ impl Render {
     }
 }
 
+const WIREFRAME_COLOR: Color = [255, 255, 255];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RenderMode {
+    Shaded,
+    Wireframe,
+    ShadedWireframe, //wireframe over shaded
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DrawLineAlgorithm {
     DDA, //Digital Differential Analyzer
     MiddlePoint,
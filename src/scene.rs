@@ -0,0 +1,283 @@
+use crate::*;
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+//a mesh, light and camera can hang on the same node, all of them move with it
+pub struct Node {
+    pub name: String,
+    pub transform: Transform,    //relative to the parent
+    pub visible: bool,           //hides the meshes of the node and its children
+    pub mesh: Option<usize>,     //index into `Scene::meshes`, nodes can share a mesh
+    pub light: Option<Light>,    //in the space of the node
+    pub camera: Option<Frustum>, //looks at -z of the node
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+}
+impl Node {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            transform: Transform::identity(),
+            visible: true,
+            mesh: None,
+            light: None,
+            camera: None,
+            parent: None,
+            children: vec![],
+        }
+    }
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.transform = transform;
+        self
+    }
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.transform.translation = translation;
+        self
+    }
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.transform.rotation = rotation;
+        self
+    }
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.transform.scale = scale;
+        self
+    }
+    pub fn with_mesh(mut self, mesh: usize) -> Self {
+        self.mesh = Some(mesh);
+        self
+    }
+    pub fn with_light(mut self, light: impl Into<Light>) -> Self {
+        self.light = Some(light.into());
+        self
+    }
+    pub fn with_camera(mut self, frustum: Frustum) -> Self {
+        self.camera = Some(frustum);
+        self
+    }
+
+    pub fn get_parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+    pub fn get_children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn get_local_matrix(&self) -> Matrix4 {
+        self.transform.to_matrix()
+    }
+}
+
+pub struct Scene {
+    nodes: Vec<Node>,
+    pub meshes: Vec<Mesh>,
+    pub lights: LightSet, //ambient, environment and lights not attached to a node
+    pub active_camera: Option<NodeId>, //None keeps the camera of the render
+}
+impl Scene {
+    pub fn new(lights: LightSet) -> Self {
+        Self {
+            nodes: vec![],
+            meshes: vec![],
+            lights,
+            active_camera: None,
+        }
+    }
+
+    pub fn add_mesh(&mut self, mesh: Mesh) -> usize {
+        self.meshes.push(mesh);
+        self.meshes.len() - 1
+    }
+    pub fn add_node(&mut self, node: Node, parent: Option<NodeId>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node);
+        self.set_parent(id, parent);
+        id
+    }
+    //the local transform is kept, so the node moves to the same offset under the new parent
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        //a node can't be moved below itself
+        let mut ancestor = parent;
+        while let Some(node) = ancestor {
+            assert!(node != id, "cycle in the scene graph");
+            ancestor = self.nodes[node.0].parent;
+        }
+
+        if let Some(old) = self.nodes[id.0].parent {
+            self.nodes[old.0].children.retain(|child| *child != id);
+        }
+        if let Some(parent) = parent {
+            self.nodes[parent.0].children.push(id);
+        }
+        self.nodes[id.0].parent = parent;
+    }
+
+    pub fn get_node(&self, id: NodeId) -> &Node {
+        &self.nodes[id.0]
+    }
+    pub fn get_node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id.0]
+    }
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes
+            .iter()
+            .position(|node| node.name == name)
+            .map(NodeId)
+    }
+    pub fn get_roots(&self) -> Vec<NodeId> {
+        (0..self.nodes.len())
+            .map(NodeId)
+            .filter(|id| self.nodes[id.0].parent.is_none())
+            .collect()
+    }
+    pub fn node_ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
+    }
+
+    //parent world * local, up to the root
+    pub fn get_world_matrix(&self, id: NodeId) -> Matrix4 {
+        let node = &self.nodes[id.0];
+        match node.parent {
+            Some(parent) => self.get_world_matrix(parent) * node.get_local_matrix(),
+            None => node.get_local_matrix(),
+        }
+    }
+    //world matrix of every node, indexed like the nodes. each matrix is computed once, top down
+    pub fn get_world_matrices(&self) -> Vec<Matrix4> {
+        let mut world = vec![Matrix4::ident(); self.nodes.len()];
+        let mut stack: Vec<(NodeId, Matrix4)> = self
+            .get_roots()
+            .into_iter()
+            .map(|id| (id, Matrix4::ident()))
+            .collect();
+        while let Some((id, parent)) = stack.pop() {
+            let node = &self.nodes[id.0];
+            world[id.0] = parent * node.get_local_matrix();
+            stack.extend(node.children.iter().map(|child| (*child, world[id.0])));
+        }
+        world
+    }
+    //world box of the meshes of every node and its children, None for subtrees without a mesh.
+    //`mesh_bounds` by mesh index, e.g. `Mesh::get_aabb`, meshes without bounds are left out
+    pub fn get_world_bounds(
+        &self,
+        world: &[Matrix4],
+        mesh_bounds: &[Option<Aabb>],
+    ) -> Vec<Option<Aabb>> {
+        let mut bounds = vec![None; self.nodes.len()];
+        //children before their parents
+        let mut order = vec![];
+        let mut stack = self.get_roots();
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.nodes[id.0].children.iter());
+        }
+        for id in order.into_iter().rev() {
+            let node = &self.nodes[id.0];
+            let own = node
+                .mesh
+                .and_then(|mesh| mesh_bounds[mesh])
+                .map(|aabb| aabb.transform(&world[id.0]));
+            bounds[id.0] = node
+                .children
+                .iter()
+                .filter_map(|child| bounds[child.0])
+                .chain(own)
+                .reduce(|a: Aabb, b| a.union(&b));
+        }
+        bounds
+    }
+    //visible nodes with a mesh in the subtree of `id`, including itself
+    fn count_mesh_nodes(&self, id: NodeId) -> usize {
+        let node = &self.nodes[id.0];
+        if !node.visible {
+            return 0;
+        }
+        node.mesh.is_some() as usize
+            + node
+                .children
+                .iter()
+                .map(|child| self.count_mesh_nodes(*child))
+                .sum::<usize>()
+    }
+    //false if the node or one of its ancestors is hidden
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        let node = &self.nodes[id.0];
+        node.visible && node.parent.is_none_or(|parent| self.is_visible(parent))
+    }
+
+    //camera of a node, placed by its world matrix
+    pub fn get_camera(&self, id: NodeId) -> Option<Camera> {
+        let frustum = self.nodes[id.0].camera.as_ref()?;
+        let world = self.get_world_matrix(id);
+        let position = world.mul(Vec3::zero().upgrade()).perspective_divide();
+        let vector = |v: Vec4| Vec3::new(v.x, v.y, v.z);
+        let forward = vector(world.mul(Vec4::new(0.0, 0.0, -1.0, 0.0)));
+        let up = vector(world.mul(Vec4::new(0.0, 1.0, 0.0, 0.0)));
+
+        let mut camera = Camera::new(frustum.clone(), position, position + forward);
+        camera.set_rotation(look_at(position, position + forward, up));
+        Some(camera)
+    }
+}
+
+impl Render {
+    //every visible mesh with the world matrix of its node, lit by `scene.lights` and the lights of
+    //the nodes. the active camera becomes the camera of the render.
+    //subtrees whose bounds are outside of the view are skipped
+    pub fn draw_scene(&mut self, scene: &Scene) -> RenderStats {
+        let world = scene.get_world_matrices();
+        if let Some(camera) = scene.active_camera.and_then(|id| scene.get_camera(id)) {
+            self.camera = camera;
+        }
+
+        let mut lights = scene.lights.clone();
+        for id in scene.node_ids() {
+            if let Some(light) = &scene.nodes[id.0].light {
+                lights.add(light.transform(&world[id.0]));
+            }
+        }
+
+        //the meshes of visible nodes are posed once, for their bounds and every node drawing them
+        let mut posed: Vec<Option<Cow<[Vertex]>>> = scene.meshes.iter().map(|_| None).collect();
+        for id in scene.node_ids().filter(|id| scene.is_visible(*id)) {
+            if let Some(mesh) = scene.nodes[id.0].mesh {
+                posed[mesh].get_or_insert_with(|| scene.meshes[mesh].get_posed_vertexes());
+            }
+        }
+        let culling = self.frustum_culling.then(|| {
+            let mesh_bounds: Vec<Option<Aabb>> = posed
+                .iter()
+                .map(|vertexes| vertexes.as_deref().and_then(Aabb::from_vertexes))
+                .collect();
+            (
+                scene.get_world_bounds(&world, &mesh_bounds),
+                self.camera.get_frustum_planes(),
+            )
+        });
+
+        let mut stats = RenderStats::default();
+        let mut stack = scene.get_roots();
+        while let Some(id) = stack.pop() {
+            let node = &scene.nodes[id.0];
+            if !node.visible {
+                continue;
+            }
+            //nothing below the node can be seen
+            if let Some((bounds, planes)) = &culling {
+                if bounds[id.0].is_some_and(|aabb| !planes.intersects_aabb(&aabb)) {
+                    stats.nodes_culled += scene.count_mesh_nodes(id);
+                    continue;
+                }
+            }
+            if let Some(mesh) = node.mesh {
+                let vertexes = posed[mesh].as_deref().unwrap();
+                stats += self.draw_posed(&scene.meshes[mesh], vertexes, &lights, world[id.0]);
+            }
+            stack.extend(node.children.iter().rev());
+        }
+
+        stats
+    }
+}
@@ -2,7 +2,7 @@ use crate::*;
 
 pub type VertexShader = Box<dyn Fn(&Vertex, &Matrix4, &Matrix4) -> Uniform>; //(world vertex for lighting, ndc position)
 pub type FragmentShader =
-    Box<dyn Fn(&Fragment, &Material, &PointLight, &Vec3, &Matrix4) -> ShadedFragment>; //(screen_pos,depth,color)
+    Box<dyn Fn(&Fragment, &Material, &LightSet, &Vec3, &Matrix4) -> ShadedFragment>; //(screen_pos,depth,color)
 pub struct Shader {
     vertex_shader: VertexShader,
     fragment_shader: FragmentShader,
@@ -28,21 +28,21 @@ impl Shader {
         &self,
         frame: &Fragment,
         material: &Material,
-        light: &PointLight,
+        lights: &LightSet,
         camera_position: &Vec3,
         model_mat: &Matrix4,
     ) -> ShadedFragment {
-        (self.fragment_shader)(frame, material, light, camera_position, model_mat)
+        (self.fragment_shader)(frame, material, lights, camera_position, model_mat)
     }
 }
 
 pub struct ShadedFragment {
     pub screen_pos: (u32, u32),
     pub depth: f32,
-    pub color: Color,
+    pub color: Vec3, //linear rgb, may be above 1
 }
 impl ShadedFragment {
-    pub fn new(screen_pos: (u32, u32), depth: f32, color: Color) -> Self {
+    pub fn new(screen_pos: (u32, u32), depth: f32, color: Vec3) -> Self {
         Self {
             screen_pos,
             depth,
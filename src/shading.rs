@@ -0,0 +1,317 @@
+use crate::*;
+use std::f32::consts::PI;
+
+//reflectance at normal incidence of dielectrics
+const DIELECTRIC_F0: f32 = 0.04;
+
+impl Shader {
+    //phong, cook-torrance or toon, chosen by `Material::material`
+    pub fn standard() -> Self {
+        Shader::new(standard_vertex_shader(), standard_fragment_shader())
+    }
+}
+
+pub fn standard_vertex_shader() -> VertexShader {
+    Box::new(|vertex: &Vertex, mvp: &Matrix4, model_mat: &Matrix4| {
+        let world_position = model_mat
+            .mul(vertex.position.upgrade())
+            .perspective_divide();
+        let world_normal = transform_normal(&normal_matrix(model_mat), vertex.normal);
+
+        let ndc_position = mvp.mul(vertex.position.upgrade()).perspective_divide();
+        Uniform::new(world_position, world_normal, ndc_position, vertex.texcoord)
+            .with_color(vertex.color)
+    })
+}
+
+pub fn standard_fragment_shader() -> FragmentShader {
+    Box::new(
+        |fragment: &Fragment,
+         material: &Material,
+         lights: &LightSet,
+         camera_position: &Vec3,
+         model_mat: &Matrix4| {
+            let surface = Surface {
+                position: fragment.world_position,
+                normal: surface_normal(fragment, material, model_mat),
+                view: (*camera_position - fragment.world_position).normalize(),
+                albedo: base_color(material, fragment),
+            };
+            if fragment.shading == ShadingModel::Gouraud {
+                let emissive = match &material.material {
+                    MaterialModel::Pbr(pbr) => pbr.sample(fragment.texcoord).emissive,
+                    _ => Vec3::zero(),
+                };
+                let color = fragment.lighting.hadamard(&surface.albedo) + emissive;
+                return ShadedFragment::new(fragment.screen_pos, fragment.depth, color);
+            }
+            let samples = lights.samples(surface.position);
+
+            let color = match &material.material {
+                MaterialModel::Phong(phong) => {
+                    let specular = specular_color(material, phong, fragment.texcoord);
+                    shade_phong(phong, specular, &surface, lights, samples)
+                }
+                MaterialModel::Pbr(pbr) => {
+                    shade_pbr(&pbr.sample(fragment.texcoord), &surface, lights, samples)
+                }
+                MaterialModel::Toon(toon) => {
+                    shade_toon(toon, &surface, lights, samples, fragment.texcoord)
+                }
+            };
+
+            ShadedFragment::new(fragment.screen_pos, fragment.depth, color)
+        },
+    )
+}
+
+//a point to be lit, in world space
+#[derive(Clone, Copy, Debug)]
+pub struct Surface {
+    pub position: Vec3,
+    pub normal: Vec3, //normalized
+    pub view: Vec3,   //surface -> camera, normalized
+    pub albedo: Vec3, //linear base colour
+}
+
+//gouraud shading, lit at the vertex with a white albedo.
+//the base colour and the emissive are applied per fragment, so textures keep their detail
+pub fn shade_vertex(
+    uniform: &Uniform,
+    material: &Material,
+    lights: &LightSet,
+    camera_position: &Vec3,
+) -> Vec3 {
+    let surface = Surface {
+        position: uniform.world_position,
+        normal: uniform.world_normal.normalize(),
+        view: (*camera_position - uniform.world_position).normalize(),
+        albedo: Vec3::new(1.0, 1.0, 1.0),
+    };
+    let samples = lights.samples(surface.position);
+    match &material.material {
+        MaterialModel::Phong(phong) => {
+            let specular = specular_color(material, phong, uniform.texcoord);
+            shade_phong(phong, specular, &surface, lights, samples)
+        }
+        MaterialModel::Pbr(pbr) => {
+            let sample = PbrSample {
+                emissive: Vec3::zero(),
+                ..pbr.sample(uniform.texcoord)
+            };
+            shade_pbr(&sample, &surface, lights, samples)
+        }
+        MaterialModel::Toon(toon) => shade_toon(toon, &surface, lights, samples, uniform.texcoord),
+    }
+}
+
+//world normal, from the normal map(model space) if there is one.
+//flat and gouraud shading keep the geometric normal, a mapped one would hide the difference
+pub fn surface_normal(fragment: &Fragment, material: &Material, model_mat: &Matrix4) -> Vec3 {
+    if let (Some(normal_map), ShadingModel::Phong) = (&material.normal, fragment.shading) {
+        let model_normal = normal_map.get_normal(fragment.texcoord);
+        transform_normal(&normal_matrix(model_mat), model_normal).normalize()
+    } else {
+        fragment.world_normal.normalize()
+    }
+}
+
+//inverse transpose of the upper 3x3 of the model matrix, without the translation. built from the
+//cofactors with the sign of the determinant, so it is the inverse transpose up to a scale and
+//tiny scales don't need an inverse. a model matrix flattened to a line or a point is used as it is
+pub fn normal_matrix(model_mat: &Matrix4) -> Matrix4 {
+    let column = |j: usize| {
+        Vec3::new(
+            model_mat.get(0, j),
+            model_mat.get(1, j),
+            model_mat.get(2, j),
+        )
+    };
+    let (a, b, c) = (column(0), column(1), column(2));
+    let cofactors = [b.cross(&c), c.cross(&a), a.cross(&b)];
+    let sign = if a.dot(&cofactors[0]) < 0.0 {
+        -1.0
+    } else {
+        1.0
+    };
+    let [x, y, z] = if cofactors.iter().all(|cofactor| cofactor.length2() == 0.0) {
+        [a, b, c]
+    } else {
+        cofactors.map(|cofactor| sign * cofactor)
+    };
+    #[rustfmt::skip]
+    let normal_mat = Matrix4::new([
+        x.x,    y.x,    z.x,    0.0,
+        x.y,    y.y,    z.y,    0.0,
+        x.z,    y.z,    z.z,    0.0,
+        0.0,    0.0,    0.0,    1.0,
+    ]);
+    normal_mat
+}
+//w = 0, normals are directions and don't move with the translation. not normalized
+pub fn transform_normal(normal_mat: &Matrix4, normal: Vec3) -> Vec3 {
+    let normal = normal_mat.mul(Vec4::new(normal.x, normal.y, normal.z, 0.0));
+    Vec3::new(normal.x, normal.y, normal.z)
+}
+
+//material colour * vertex colour
+pub fn base_color(material: &Material, fragment: &Fragment) -> Vec3 {
+    let base: Vec3 = match &material.base {
+        BaseColor::Color(color) => decode_srgb(*color),
+        BaseColor::Map(map) => map.get_color(fragment.texcoord),
+    };
+    let base = base.hadamard(&Vec3::new(
+        fragment.color.x,
+        fragment.color.y,
+        fragment.color.z,
+    ));
+    match &material.material {
+        MaterialModel::Pbr(pbr) => base.hadamard(&pbr.base_color),
+        _ => base,
+    }
+}
+
+//phong only, from the specular map if there is one
+pub fn specular_color(material: &Material, phong: &PhongMaterial, texcoord: Vec2) -> Vec3 {
+    match &material.specular {
+        Some(specular_map) => specular_map.get_specular(texcoord),
+        None => phong.specular,
+    }
+}
+
+//n,v,l: normal, surface -> camera, surface -> light, all normalized
+pub fn blinn_phong(material: &PhongMaterial, specular: Vec3, n: Vec3, v: Vec3, l: Vec3) -> Vec3 {
+    let h = (v + l).normalize();
+    let diffuse_intensity = n.dot(&l).max(0.0) * material.diffuse;
+    let specular_intensity = n.dot(&h).max(0.0).powf(material.specular_shininess) * specular;
+    diffuse_intensity + specular_intensity
+}
+
+//lights: ambient light and environment, samples: the lights which may reach the surface
+pub fn shade_phong(
+    material: &PhongMaterial,
+    specular: Vec3,
+    surface: &Surface,
+    lights: &LightSet,
+    samples: impl Iterator<Item = LightSample>,
+) -> Vec3 {
+    let (n, v) = (surface.normal, surface.view);
+    let mut light_intensity = lights.get_ambient(n).hadamard(&material.ambient);
+    for sample in samples {
+        light_intensity +=
+            sample
+                .radiance
+                .hadamard(&blinn_phong(material, specular, n, v, sample.direction));
+    }
+    light_intensity.hadamard(&surface.albedo)
+}
+
+//GGX normal distribution, alpha = roughness^2
+pub fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a2 = roughness.powi(4);
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+//Smith geometry term with Schlick-GGX, k for direct lighting
+pub fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    let k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    let schlick_ggx = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+    schlick_ggx(n_dot_v) * schlick_ggx(n_dot_l)
+}
+
+pub fn fresnel_schlick(cos_theta: f32, f0: Vec3) -> Vec3 {
+    let t = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    f0 + t * (Vec3::new(1.0, 1.0, 1.0) - f0)
+}
+
+//fresnel averaged over the microfacets, for image based lighting where there is no half vector
+pub fn fresnel_schlick_roughness(cos_theta: f32, f0: Vec3, roughness: f32) -> Vec3 {
+    let t = (1.0 - cos_theta).clamp(0.0, 1.0).powi(5);
+    let max = 1.0 - roughness;
+    f0 + t * (f0.max(&Vec3::new(max, max, max)) - f0)
+}
+
+pub fn specular_f0(albedo: Vec3, metallic: f32) -> Vec3 {
+    let dielectric = Vec3::new(DIELECTRIC_F0, DIELECTRIC_F0, DIELECTRIC_F0);
+    (1.0 - metallic) * dielectric + metallic * albedo
+}
+
+//cook-torrance brdf * cos, the radiance of the light is multiplied outside
+pub fn cook_torrance(
+    albedo: Vec3,
+    metallic: f32,
+    roughness: f32,
+    n: Vec3,
+    v: Vec3,
+    l: Vec3,
+) -> Vec3 {
+    let n_dot_l = n.dot(&l);
+    let n_dot_v = n.dot(&v).max(1e-4);
+    if n_dot_l <= 0.0 {
+        return Vec3::zero();
+    }
+    let h = (v + l).normalize();
+    let n_dot_h = n.dot(&h).max(0.0);
+
+    let f = fresnel_schlick(h.dot(&v).max(0.0), specular_f0(albedo, metallic));
+    let d = distribution_ggx(n_dot_h, roughness);
+    let g = geometry_smith(n_dot_v, n_dot_l, roughness);
+    let specular = (d * g / (4.0 * n_dot_v * n_dot_l + 1e-4)) * f;
+
+    //energy not reflected is refracted, metals absorb all of it
+    let kd = (1.0 - metallic) * (Vec3::new(1.0, 1.0, 1.0) - f);
+    let diffuse = (1.0 / PI) * kd.hadamard(&albedo);
+
+    n_dot_l * (diffuse + specular)
+}
+
+pub fn shade_pbr(
+    material: &PbrSample,
+    surface: &Surface,
+    lights: &LightSet,
+    samples: impl Iterator<Item = LightSample>,
+) -> Vec3 {
+    let (n, v, albedo) = (surface.normal, surface.view, surface.albedo);
+    let (metallic, roughness) = (material.metallic, material.roughness);
+
+    let mut color = material.emissive
+        + material.ao
+            * match &lights.environment {
+                Some(environment) => shade_ibl(environment, albedo, metallic, roughness, n, v),
+                None => lights.ambient.hadamard(&albedo),
+            };
+    for sample in samples {
+        color += sample.radiance.hadamard(&cook_torrance(
+            albedo,
+            metallic,
+            roughness,
+            n,
+            v,
+            sample.direction,
+        ));
+    }
+    color
+}
+
+//split sum image based lighting, diffuse from the irradiance and specular from the prefiltered map
+pub fn shade_ibl(
+    environment: &Ibl,
+    albedo: Vec3,
+    metallic: f32,
+    roughness: f32,
+    n: Vec3,
+    v: Vec3,
+) -> Vec3 {
+    let n_dot_v = n.dot(&v).max(1e-4);
+    let f = fresnel_schlick_roughness(n_dot_v, specular_f0(albedo, metallic), roughness);
+    let kd = (1.0 - metallic) * (Vec3::new(1.0, 1.0, 1.0) - f);
+    let diffuse = kd.hadamard(&albedo).hadamard(&environment.irradiance(n));
+
+    let r = 2.0 * n_dot_v * n - v;
+    let brdf = environment.brdf(n_dot_v, roughness);
+    let specular = environment
+        .specular(r, roughness)
+        .hadamard(&(brdf.x * f + Vec3::new(brdf.y, brdf.y, brdf.y)));
+    diffuse + specular
+}
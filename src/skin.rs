@@ -0,0 +1,199 @@
+use crate::*;
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SkinningMethod {
+    #[default]
+    Linear, //blends the joint matrices, joints that twist lose volume
+    DualQuaternion, //blends rigid transforms, keeps the volume but ignores the joint scale
+}
+
+#[derive(Clone, Debug)]
+pub struct Joint {
+    pub name: String,
+    pub parent: Option<usize>,
+    pub transform: Transform,  //pose relative to the parent, animated
+    pub inverse_bind: Matrix4, //mesh space -> joint space in the bind pose
+}
+impl Joint {
+    pub fn new(
+        name: &str,
+        parent: Option<usize>,
+        transform: Transform,
+        inverse_bind: Matrix4,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            parent,
+            transform,
+            inverse_bind,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Skeleton {
+    pub joints: Vec<Joint>,
+    pub root: Matrix4, //transform of everything above the root joints
+    pub method: SkinningMethod,
+}
+impl Skeleton {
+    pub fn new(joints: Vec<Joint>) -> Self {
+        Self {
+            joints,
+            root: Matrix4::ident(),
+            method: SkinningMethod::default(),
+        }
+    }
+    pub fn with_root(mut self, root: Matrix4) -> Self {
+        self.root = root;
+        self
+    }
+    pub fn with_method(mut self, method: SkinningMethod) -> Self {
+        self.method = method;
+        self
+    }
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.joints.iter().position(|joint| joint.name == name)
+    }
+
+    //the pose of every joint in mesh space
+    pub fn get_world_matrices(&self) -> Vec<Matrix4> {
+        let mut world: Vec<Option<Matrix4>> = vec![None; self.joints.len()];
+        fn resolve(skeleton: &Skeleton, world: &mut [Option<Matrix4>], joint: usize) -> Matrix4 {
+            if let Some(matrix) = world[joint] {
+                return matrix;
+            }
+            let parent = match skeleton.joints[joint].parent {
+                Some(parent) => resolve(skeleton, world, parent),
+                None => skeleton.root,
+            };
+            let matrix = parent * skeleton.joints[joint].transform.to_matrix();
+            world[joint] = Some(matrix);
+            matrix
+        }
+        (0..self.joints.len())
+            .map(|joint| resolve(self, &mut world, joint))
+            .collect()
+    }
+    //world * inverse bind, moves a vertex from the bind pose into the current pose
+    pub fn get_joint_matrices(&self) -> Vec<Matrix4> {
+        self.get_world_matrices()
+            .into_iter()
+            .zip(self.joints.iter())
+            .map(|(world, joint)| world * joint.inverse_bind)
+            .collect()
+    }
+
+    //vertexes without weights are left in place
+    pub fn skin(&self, vertexes: &[Vertex]) -> Vec<Vertex> {
+        let matrices = self.get_joint_matrices();
+        match self.method {
+            SkinningMethod::Linear => vertexes
+                .iter()
+                .map(|vertex| skin_linear(vertex, &matrices))
+                .collect(),
+            SkinningMethod::DualQuaternion => {
+                let dual_quats: Vec<DualQuat> =
+                    matrices.iter().map(DualQuat::from_matrix).collect();
+                vertexes
+                    .iter()
+                    .map(|vertex| skin_dual_quat(vertex, &dual_quats))
+                    .collect()
+            }
+        }
+    }
+}
+
+fn skin_linear(vertex: &Vertex, matrices: &[Matrix4]) -> Vertex {
+    let mut position = Vec4::new(0.0, 0.0, 0.0, 0.0);
+    let mut normal = Vec4::new(0.0, 0.0, 0.0, 0.0);
+    let mut total = 0.0;
+    for (joint, weight) in vertex.joints.iter().zip(vertex.weights) {
+        if weight <= 0.0 {
+            continue;
+        }
+        let matrix = weight * matrices[*joint as usize];
+        position = position + matrix.mul(vertex.position.upgrade());
+        normal = normal
+            + matrix.mul(Vec4::new(
+                vertex.normal.x,
+                vertex.normal.y,
+                vertex.normal.z,
+                0.0,
+            ));
+        total += weight;
+    }
+    if total <= 0.0 {
+        return *vertex;
+    }
+    //weights are expected to add up to 1, the position is divided by the sum anyway
+    Vertex {
+        position: position.perspective_divide(),
+        //the blended matrix instead of its inverse transpose, exact for rigid joints and uniform scale
+        normal: Vec3::new(normal.x, normal.y, normal.z).normalize(),
+        ..*vertex
+    }
+}
+
+//rotation r and translation t as r + ε(t·r/2)
+#[derive(Clone, Copy, Debug)]
+struct DualQuat {
+    real: Quat,
+    dual: Quat,
+}
+impl DualQuat {
+    fn from_matrix(matrix: &Matrix4) -> Self {
+        let real = Quat::from_mat4(matrix);
+        let t = Quat::new(0.0, matrix.get(0, 3), matrix.get(1, 3), matrix.get(2, 3));
+        Self {
+            real,
+            dual: 0.5 * (t * real),
+        }
+    }
+}
+
+fn skin_dual_quat(vertex: &Vertex, dual_quats: &[DualQuat]) -> Vertex {
+    let mut real = Quat::new(0.0, 0.0, 0.0, 0.0);
+    let mut dual = Quat::new(0.0, 0.0, 0.0, 0.0);
+    let mut pivot = None;
+    for (joint, weight) in vertex.joints.iter().zip(vertex.weights) {
+        if weight <= 0.0 {
+            continue;
+        }
+        let dual_quat = dual_quats[*joint as usize];
+        //q and -q are the same rotation, blend on the side of the first joint
+        let pivot = *pivot.get_or_insert(dual_quat.real);
+        let weight = if pivot.dot(&dual_quat.real) < 0.0 {
+            -weight
+        } else {
+            weight
+        };
+        real = real + weight * dual_quat.real;
+        dual = dual + weight * dual_quat.dual;
+    }
+    if pivot.is_none() {
+        return *vertex;
+    }
+
+    let length = real.length2().sqrt();
+    let (real, dual) = ((1.0 / length) * real, (1.0 / length) * dual);
+    //t = 2·dual·conj(real)
+    let translation = (dual * real.conjugate()).vector();
+    Vertex {
+        position: real.rotate(vertex.position) + 2.0 * translation,
+        normal: real.rotate(vertex.normal).normalize(),
+        ..*vertex
+    }
+}
+
+impl Mesh {
+    //the vertexes with the morph targets blended in, in the current pose of the skeleton
+    pub fn get_posed_vertexes(&self) -> Cow<'_, [Vertex]> {
+        let vertexes = self.get_morphed_vertexes();
+        match &self.skeleton {
+            Some(skeleton) => Cow::Owned(skeleton.skin(&vertexes)),
+            None => vertexes,
+        }
+    }
+}
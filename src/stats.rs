@@ -0,0 +1,95 @@
+use std::ops::AddAssign;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderStats {
+    pub meshes_culled: usize, //draws skipped because the bounds of the mesh are outside of the view
+    pub nodes_culled: usize,  //scene nodes with a mesh skipped with their whole subtree
+    pub vertices_shaded: usize,
+    pub triangles_assembled: usize,
+    pub triangles_back_face_culled: usize,
+    pub triangles_ndc_culled: usize, //dropped by the near/far/side planes, there is no clipping yet
+    pub triangles_rasterized: usize,
+    pub fragments_generated: usize,
+    pub depth_test_passed: usize,
+    pub depth_test_failed: usize,
+
+    pub vertex_shading_time: Duration,
+    pub assembly_time: Duration, //primitive assembly, culling and screen mapping
+    pub raster_time: Duration,
+    pub fragment_shading_time: Duration,
+    pub merge_time: Duration,
+    pub resolve_time: Duration, //post stack and tone mapping, only in the frame stats
+}
+impl RenderStats {
+    pub fn total_time(&self) -> Duration {
+        self.vertex_shading_time
+            + self.assembly_time
+            + self.raster_time
+            + self.fragment_shading_time
+            + self.merge_time
+            + self.resolve_time
+    }
+}
+impl AddAssign<RenderStats> for RenderStats {
+    fn add_assign(&mut self, rhs: RenderStats) {
+        self.meshes_culled += rhs.meshes_culled;
+        self.nodes_culled += rhs.nodes_culled;
+        self.vertices_shaded += rhs.vertices_shaded;
+        self.triangles_assembled += rhs.triangles_assembled;
+        self.triangles_back_face_culled += rhs.triangles_back_face_culled;
+        self.triangles_ndc_culled += rhs.triangles_ndc_culled;
+        self.triangles_rasterized += rhs.triangles_rasterized;
+        self.fragments_generated += rhs.fragments_generated;
+        self.depth_test_passed += rhs.depth_test_passed;
+        self.depth_test_failed += rhs.depth_test_failed;
+
+        self.vertex_shading_time += rhs.vertex_shading_time;
+        self.assembly_time += rhs.assembly_time;
+        self.raster_time += rhs.raster_time;
+        self.fragment_shading_time += rhs.fragment_shading_time;
+        self.merge_time += rhs.merge_time;
+        self.resolve_time += rhs.resolve_time;
+    }
+}
+
+//wall clock of one pipeline stage, also a `tracing` span with the `tracing` feature
+pub(crate) struct Stage {
+    start: Instant,
+    #[cfg(feature = "tracing")]
+    _span: tracing::span::EnteredSpan,
+}
+impl Stage {
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(span: tracing::span::EnteredSpan) -> Self {
+        Self {
+            start: Instant::now(),
+            _span: span,
+        }
+    }
+
+    pub(crate) fn end(self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+//span names have to be literals, so stages are started through a macro
+#[cfg(not(feature = "tracing"))]
+macro_rules! stage {
+    ($name:literal) => {
+        Stage::new()
+    };
+}
+#[cfg(feature = "tracing")]
+macro_rules! stage {
+    ($name:literal) => {
+        Stage::new(tracing::trace_span!($name).entered())
+    };
+}
+pub(crate) use stage;
@@ -0,0 +1,135 @@
+use crate::*;
+use std::mem::replace;
+
+//what a colour attachment of a render target receives
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attachment {
+    Color,    //output of the fragment shader, linear
+    Position, //world position
+    Normal,   //interpolated world normal
+    Texcoord, //(u, v, 0)
+    Depth,    //ndc depth in every channel, bigger is closer like the depth buffer
+}
+
+//off-screen colour attachments sharing one depth buffer
+pub struct RenderTarget {
+    width: u32,
+    height: u32,
+    attachments: Vec<(Attachment, HdrFrameBuffer)>,
+    depth_buffer: DepthBuffer,
+}
+impl RenderTarget {
+    pub fn new(width: u32, height: u32, attachments: &[Attachment]) -> Self {
+        Self {
+            width,
+            height,
+            attachments: attachments
+                .iter()
+                .map(|attachment| {
+                    (
+                        *attachment,
+                        HdrFrameBuffer::new(width, height, Vec3::zero()),
+                    )
+                })
+                .collect(),
+            depth_buffer: DepthBuffer::new(width, height, -1.0),
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+    pub fn get_attachment(&self, index: usize) -> &HdrFrameBuffer {
+        &self.attachments[index].1
+    }
+    pub fn get_depth_buffer(&self) -> &DepthBuffer {
+        &self.depth_buffer
+    }
+    //the attachment as a texture, e.g. for the base colour or emissive map of the next pass
+    pub fn get_texture(&self, index: usize) -> Texture {
+        Texture::from_hdr(self.get_attachment(index))
+    }
+
+    //`Color` attachments to `color`, everything else to zero
+    pub fn clear(&mut self, color: Vec3) {
+        for (attachment, buffer) in self.attachments.iter_mut() {
+            match attachment {
+                Attachment::Color => buffer.reset(color),
+                Attachment::Depth => buffer.reset(Vec3::new(-1.0, -1.0, -1.0)),
+                _ => buffer.reset(Vec3::zero()),
+            }
+        }
+        self.depth_buffer.reset(-1.0);
+    }
+}
+
+impl Render {
+    //draw into an off-screen target as seen from `camera`, the frame of the render is untouched
+    pub fn draw_to_target(
+        &mut self,
+        target: &mut RenderTarget,
+        camera: &Camera,
+        mesh: &Mesh,
+        lights: &LightSet,
+        model_mat: Matrix4,
+    ) -> RenderStats {
+        let mut stats = RenderStats::default();
+        //the pipeline maps to the size of the render, borrow the target's for this draw
+        let camera = replace(&mut self.camera, camera.clone());
+        let width = replace(&mut self.width, target.width);
+        let height = replace(&mut self.height, target.height);
+
+        let triangles = self.assemble(mesh, model_mat, Some(lights), &mut stats);
+        let (_, fragments) = self.rasterize(triangles, &mut stats);
+
+        let stage = stage!("fragment_shading");
+        let camera_position = self.camera.get_position();
+        for fragment in fragments.iter() {
+            let (x, y) = fragment.screen_pos;
+            //early depth test, only visible fragments are shaded
+            if !target
+                .depth_buffer
+                .get_depth(x, y)
+                .is_some_and(|depth| fragment.depth > depth)
+            {
+                stats.depth_test_failed += 1;
+                continue;
+            }
+            target.depth_buffer.set_depth(x, y, fragment.depth);
+            stats.depth_test_passed += 1;
+
+            for (attachment, buffer) in target.attachments.iter_mut() {
+                let value = match attachment {
+                    Attachment::Color => {
+                        self.shader
+                            .run_fragment_shader(
+                                fragment,
+                                &mesh.material,
+                                lights,
+                                &camera_position,
+                                &model_mat,
+                            )
+                            .color
+                    }
+                    Attachment::Position => fragment.world_position,
+                    Attachment::Normal => fragment.world_normal.normalize(),
+                    Attachment::Texcoord => {
+                        Vec3::new(fragment.texcoord.x, fragment.texcoord.y, 0.0)
+                    }
+                    Attachment::Depth => Vec3::new(fragment.depth, fragment.depth, fragment.depth),
+                };
+                buffer.set_color(x, y, value);
+            }
+        }
+        stats.fragment_shading_time = stage.end();
+
+        self.camera = camera;
+        self.width = width;
+        self.height = height;
+        self.frame_stats += stats;
+        stats
+    }
+}
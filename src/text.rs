@@ -0,0 +1,346 @@
+use crate::*;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::sync::OnceLock;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextAlign {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Clone, Debug)]
+pub struct Glyph {
+    pub width: u32,
+    pub height: u32,
+    pub x_offset: i32, //pen position -> left of bitmap
+    pub y_offset: i32, //top of line -> top of bitmap
+    pub advance: u32,
+    bitmap: Vec<bool>, //row major
+}
+impl Glyph {
+    pub fn new(
+        width: u32,
+        height: u32,
+        x_offset: i32,
+        y_offset: i32,
+        advance: u32,
+        bitmap: Vec<bool>,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            x_offset,
+            y_offset,
+            advance,
+            bitmap,
+        }
+    }
+
+    pub fn get(&self, x: u32, y: u32) -> bool {
+        self.bitmap[(y * self.width + x) as usize]
+    }
+}
+
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    line_height: u32,
+}
+impl Font {
+    pub fn new(glyphs: HashMap<char, Glyph>, line_height: u32) -> Self {
+        Self {
+            glyphs,
+            line_height,
+        }
+    }
+
+    //5x7 glyphs in a 6x8 cell, printable ascii only
+    pub fn embedded() -> &'static Font {
+        static EMBEDDED: OnceLock<Font> = OnceLock::new();
+        EMBEDDED.get_or_init(|| {
+            let glyphs = EMBEDDED_GLYPHS
+                .iter()
+                .enumerate()
+                .map(|(i, columns)| {
+                    let mut bitmap = vec![false; 5 * 7];
+                    for (x, column) in columns.iter().enumerate() {
+                        for y in 0..7 {
+                            bitmap[y * 5 + x] = (column >> y) & 1 == 1;
+                        }
+                    }
+                    let ch = char::from(b' ' + i as u8);
+                    (ch, Glyph::new(5, 7, 0, 0, 6, bitmap))
+                })
+                .collect();
+            Font::new(glyphs, 8)
+        })
+    }
+
+    pub fn load_bdf(path: &str) -> Self {
+        Self::parse_bdf(&read_to_string(path).unwrap())
+    }
+
+    pub fn parse_bdf(source: &str) -> Self {
+        let numbers =
+            |args: &[&str]| -> Vec<i32> { args.iter().map(|arg| arg.parse().unwrap()).collect() };
+
+        let mut ascent = None;
+        let mut descent = None;
+        let mut bounding = [0, 0, 0, 0];
+        let mut glyphs = HashMap::new();
+
+        //per glyph state
+        let mut encoding = None;
+        let mut advance = 0;
+        let mut bbx = [0, 0, 0, 0];
+        let mut rows: Option<Vec<&str>> = None;
+
+        for line in source.lines() {
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let args: Vec<&str> = words.collect();
+
+            if let Some(rows) = rows.as_mut() {
+                if keyword != "ENDCHAR" {
+                    rows.push(keyword);
+                    continue;
+                }
+            }
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    let values = numbers(&args);
+                    bounding = [values[0], values[1], values[2], values[3]];
+                }
+                "FONT_ASCENT" => ascent = Some(numbers(&args)[0]),
+                "FONT_DESCENT" => descent = Some(numbers(&args)[0]),
+                "STARTCHAR" => {
+                    encoding = None;
+                    advance = bounding[0];
+                    bbx = bounding;
+                }
+                "ENCODING" => encoding = char::from_u32(numbers(&args)[0] as u32),
+                "DWIDTH" => advance = numbers(&args)[0],
+                "BBX" => {
+                    let values = numbers(&args);
+                    bbx = [values[0], values[1], values[2], values[3]];
+                }
+                "BITMAP" => rows = Some(vec![]),
+                "ENDCHAR" => {
+                    let hex_rows = rows.take().unwrap_or_default();
+                    let (width, height) = (bbx[0].max(0) as u32, bbx[1].max(0) as u32);
+                    let mut bitmap = vec![false; (width * height) as usize];
+                    for (y, hex) in hex_rows.iter().take(height as usize).enumerate() {
+                        //every row is padded to whole bytes, msb is the left most pixel
+                        let bits = u128::from_str_radix(hex, 16).unwrap();
+                        let n_bits = hex.len() as u32 * 4;
+                        for x in 0..width.min(n_bits) {
+                            bitmap[y * width as usize + x as usize] =
+                                (bits >> (n_bits - 1 - x)) & 1 == 1;
+                        }
+                    }
+                    if let Some(ch) = encoding {
+                        //bbx offset is relative to the baseline, y up
+                        let font_ascent = ascent.unwrap_or(bounding[1] + bounding[3]);
+                        let y_offset = font_ascent - (bbx[3] + bbx[1]);
+                        glyphs.insert(
+                            ch,
+                            Glyph::new(
+                                width,
+                                height,
+                                bbx[2],
+                                y_offset,
+                                advance.max(0) as u32,
+                                bitmap,
+                            ),
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let line_height = match (ascent, descent) {
+            (Some(ascent), Some(descent)) => ascent + descent,
+            _ => bounding[1],
+        };
+        Font::new(glyphs, line_height.max(1) as u32)
+    }
+
+    pub fn get_glyph(&self, ch: char) -> Option<&Glyph> {
+        self.glyphs.get(&ch).or_else(|| self.glyphs.get(&'?'))
+    }
+    pub fn get_line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    pub fn line_width(&self, line: &str) -> u32 {
+        line.chars()
+            .filter_map(|ch| self.get_glyph(ch))
+            .map(|glyph| glyph.advance)
+            .sum()
+    }
+
+    //(width,height) of the text block without scaling
+    pub fn measure(&self, text: &str) -> (u32, u32) {
+        let width = text
+            .lines()
+            .map(|line| self.line_width(line))
+            .max()
+            .unwrap_or(0);
+        let height = text.lines().count() as u32 * self.line_height;
+        (width, height)
+    }
+}
+
+impl FrameBuffer {
+    pub fn draw_text(&mut self, pos: Vec2, text: &str, color: Color, scale: u32) {
+        self.draw_text_with(Font::embedded(), pos, text, color, scale, TextAlign::Left)
+    }
+
+    //pos is the top of the first line, x is the left edge/center/right edge decided by align
+    pub fn draw_text_with(
+        &mut self,
+        font: &Font,
+        pos: Vec2,
+        text: &str,
+        color: Color,
+        scale: u32,
+        align: TextAlign,
+    ) {
+        let scale = scale.max(1) as i32;
+        let mut line_top = pos.y as i32;
+        for line in text.lines() {
+            let line_width = font.line_width(line) as i32 * scale;
+            let mut pen_x = match align {
+                TextAlign::Left => pos.x as i32,
+                TextAlign::Center => pos.x as i32 - line_width / 2,
+                TextAlign::Right => pos.x as i32 - line_width,
+            };
+            for ch in line.chars() {
+                let Some(glyph) = font.get_glyph(ch) else {
+                    continue;
+                };
+                let left = pen_x + glyph.x_offset * scale;
+                let top = line_top + glyph.y_offset * scale;
+                for y in 0..glyph.height {
+                    for x in 0..glyph.width {
+                        if !glyph.get(x, y) {
+                            continue;
+                        }
+                        let px = left + x as i32 * scale;
+                        let py = top + y as i32 * scale;
+                        for dy in 0..scale {
+                            for dx in 0..scale {
+                                self.draw_pixel_checked(px + dx, py + dy, color);
+                            }
+                        }
+                    }
+                }
+                pen_x += glyph.advance as i32 * scale;
+            }
+            line_top += font.get_line_height() as i32 * scale;
+        }
+    }
+}
+
+#[rustfmt::skip]
+const EMBEDDED_GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // space
+    [0x00, 0x00, 0x5F, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7F, 0x14, 0x7F, 0x14], // #
+    [0x24, 0x2A, 0x7F, 0x2A, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x55, 0x22, 0x50], // &
+    [0x00, 0x05, 0x03, 0x00, 0x00], // '
+    [0x00, 0x1C, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1C, 0x00], // )
+    [0x08, 0x2A, 0x1C, 0x2A, 0x08], // *
+    [0x08, 0x08, 0x3E, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3E, 0x51, 0x49, 0x45, 0x3E], // 0
+    [0x00, 0x42, 0x7F, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4B, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7F, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3C, 0x4A, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1E], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3E], // @
+    [0x7E, 0x11, 0x11, 0x11, 0x7E], // A
+    [0x7F, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3E, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7F, 0x41, 0x41, 0x22, 0x1C], // D
+    [0x7F, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7F, 0x09, 0x09, 0x01, 0x01], // F
+    [0x3E, 0x41, 0x41, 0x51, 0x32], // G
+    [0x7F, 0x08, 0x08, 0x08, 0x7F], // H
+    [0x00, 0x41, 0x7F, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3F, 0x01], // J
+    [0x7F, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7F, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7F, 0x02, 0x04, 0x02, 0x7F], // M
+    [0x7F, 0x04, 0x08, 0x10, 0x7F], // N
+    [0x3E, 0x41, 0x41, 0x41, 0x3E], // O
+    [0x7F, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3E, 0x41, 0x51, 0x21, 0x5E], // Q
+    [0x7F, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7F, 0x01, 0x01], // T
+    [0x3F, 0x40, 0x40, 0x40, 0x3F], // U
+    [0x1F, 0x20, 0x40, 0x20, 0x1F], // V
+    [0x7F, 0x20, 0x18, 0x20, 0x7F], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x03, 0x04, 0x78, 0x04, 0x03], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7F, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7F, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7F, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7F], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7E, 0x09, 0x01, 0x02], // f
+    [0x08, 0x14, 0x54, 0x54, 0x3C], // g
+    [0x7F, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7D, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3D, 0x00], // j
+    [0x00, 0x7F, 0x10, 0x28, 0x44], // k
+    [0x00, 0x41, 0x7F, 0x40, 0x00], // l
+    [0x7C, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7C, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7C, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7C], // q
+    [0x7C, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3F, 0x44, 0x40, 0x20], // t
+    [0x3C, 0x40, 0x40, 0x20, 0x7C], // u
+    [0x1C, 0x20, 0x40, 0x20, 0x1C], // v
+    [0x3C, 0x40, 0x30, 0x40, 0x3C], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0C, 0x50, 0x50, 0x50, 0x3C], // y
+    [0x44, 0x64, 0x54, 0x4C, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7F, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
@@ -1,14 +1,53 @@
 use crate::*;
-use image::{open, ImageBuffer, Rgb};
+use image::{open, DynamicImage, ImageBuffer, Rgb};
 
+//texels are stored as linear f32
+#[derive(Clone, Debug)]
 pub struct Texture {
-    buffer: ImageBuffer<Rgb<u8>, Vec<u8>>,
+    buffer: ImageBuffer<Rgb<f32>, Vec<f32>>,
     width: u32,
     height: u32,
 }
 impl Texture {
+    //data which is already linear, e.g. normals or roughness
     pub fn load(path: &str) -> Self {
-        let buffer = open(path).unwrap().into_rgb8();
+        Self::from_buffer(open(path).unwrap().into_rgb32f())
+    }
+    //colour images, decoded from srgb
+    pub fn load_srgb(path: &str) -> Self {
+        let image = open(path).unwrap();
+        //hdr formats are linear already
+        if matches!(
+            image,
+            DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_)
+        ) {
+            return Self::from_buffer(image.into_rgb32f());
+        }
+        let srgb = image.into_rgb8();
+        let buffer = ImageBuffer::from_fn(srgb.width(), srgb.height(), |x, y| {
+            let color = decode_srgb(srgb.get_pixel(x, y).0);
+            Rgb([color.x, color.y, color.z])
+        });
+        Self::from_buffer(buffer)
+    }
+    //e.g. an attachment of a render target
+    pub fn from_hdr(hdr_buffer: &HdrFrameBuffer) -> Self {
+        let buffer =
+            ImageBuffer::from_fn(hdr_buffer.get_width(), hdr_buffer.get_height(), |x, y| {
+                let color = hdr_buffer.get_color(x, y).unwrap();
+                Rgb([color.x, color.y, color.z])
+            });
+        Self::from_buffer(buffer)
+    }
+    //procedural textures, f(x, y) with y going down like in an image
+    pub fn from_fn(width: u32, height: u32, f: impl Fn(u32, u32) -> Vec3) -> Self {
+        let buffer = ImageBuffer::from_fn(width, height, |x, y| {
+            let color = f(x, y);
+            Rgb([color.x, color.y, color.z])
+        });
+        Self::from_buffer(buffer)
+    }
+    fn from_buffer(buffer: ImageBuffer<Rgb<f32>, Vec<f32>>) -> Self {
         let width = buffer.width();
         let height = buffer.height();
         Self {
@@ -22,8 +61,8 @@ impl Texture {
     pub fn get_pixel(&self, texcoord: Vec2) -> Vec3 {
         self.buffer
             .get_pixel(
-                (texcoord.x * self.width as f32) as u32,
-                ((1.0 - texcoord.y) * self.height as f32) as u32, //纹理和图片的映射 1-y
+                ((texcoord.x * self.width as f32) as u32).min(self.width - 1),
+                (((1.0 - texcoord.y) * self.height as f32) as u32).min(self.height - 1), //纹理和图片的映射 1-y
             )
             .0
             .into()
@@ -40,9 +79,12 @@ pub struct BaseColorMap {
 impl BaseColorMap {
     pub fn new(path: &str) -> Self {
         BaseColorMap {
-            texture: Texture::load(path),
+            texture: Texture::load_srgb(path),
         }
     }
+    pub fn from_texture(texture: Texture) -> Self {
+        BaseColorMap { texture }
+    }
 
     pub fn get_color(&self, texcoord: Vec2) -> Vec3 {
         self.texture.get_pixel(texcoord)
@@ -84,3 +126,44 @@ impl SpecularMap {
         self.texture.get_pixel(texcoord)
     }
 }
+
+//single channel of a texture, e.g. metallic, roughness or ambient occlusion
+pub struct ScalarMap {
+    texture: Texture,
+    channel: usize, //0 r, 1 g, 2 b
+}
+impl ScalarMap {
+    pub fn new(path: &str, channel: usize) -> Self {
+        ScalarMap {
+            texture: Texture::load(path),
+            channel,
+        }
+    }
+
+    pub fn get_value(&self, texcoord: Vec2) -> f32 {
+        let pixel = self.texture.get_pixel(texcoord);
+        match self.channel {
+            0 => pixel.x,
+            1 => pixel.y,
+            _ => pixel.z,
+        }
+    }
+}
+
+pub struct EmissiveMap {
+    texture: Texture,
+}
+impl EmissiveMap {
+    pub fn new(path: &str) -> Self {
+        EmissiveMap {
+            texture: Texture::load_srgb(path),
+        }
+    }
+    pub fn from_texture(texture: Texture) -> Self {
+        EmissiveMap { texture }
+    }
+
+    pub fn get_emissive(&self, texcoord: Vec2) -> Vec3 {
+        self.texture.get_pixel(texcoord)
+    }
+}
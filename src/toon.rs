@@ -0,0 +1,255 @@
+use crate::*;
+use std::collections::HashMap;
+
+const SPECULAR_THRESHOLD: f32 = 0.5; //blinn-phong term where the hard highlight starts
+const OUTLINE_OFFSET: f32 = 0.01; //fraction of the distance to the camera
+const HATCH_DARKNESS: f32 = 0.6; //how much one stroke darkens the paper
+const HATCH_LIGHTEST: f32 = 1.0; //mean tone of the first level, blank paper
+const HATCH_DARKEST: f32 = 0.15;
+
+//n·l -> light intensity
+#[derive(Clone, Debug)]
+pub enum ToonRamp {
+    Bands(u32),   //n·l quantized into this many steps
+    Map(Texture), //horizontal strip, left is unlit, can tint the shadows
+}
+impl ToonRamp {
+    pub fn apply(&self, n_dot_l: f32) -> Vec3 {
+        let n_dot_l = n_dot_l.clamp(0.0, 1.0);
+        match self {
+            ToonRamp::Bands(bands) => {
+                let bands = (*bands).max(1) as f32;
+                let value = (n_dot_l * bands).ceil() / bands;
+                Vec3::new(value, value, value)
+            }
+            ToonRamp::Map(texture) => texture.get_pixel(Vec2::new(n_dot_l, 0.5)),
+        }
+    }
+}
+
+//tonal art map, hatching textures from light to dark, every level contains the strokes of the lighter ones
+#[derive(Clone, Debug)]
+pub struct TonalArtMap {
+    levels: Vec<Texture>,
+    scale: f32, //repetitions per texcoord unit
+}
+impl TonalArtMap {
+    pub fn new(levels: Vec<Texture>) -> Self {
+        assert!(!levels.is_empty());
+        Self { levels, scale: 1.0 }
+    }
+    pub fn load(paths: &[&str]) -> Self {
+        Self::new(paths.iter().map(|path| Texture::load(path)).collect())
+    }
+    //strokes are added to a square tile until it is as dark as the level, horizontal strokes first,
+    //then vertical and diagonal ones for cross hatching
+    pub fn generate(size: u32, levels: u32) -> Self {
+        assert!(size >= 2, "tonal art map of {size}x{size} pixels");
+        let n = (size * size) as usize;
+        let mut paper = vec![1.0f32; n];
+        let mut sum = n as f32;
+        let mut stroke = 0;
+        let mut textures = vec![];
+        for level in 0..levels.max(2) {
+            let t = level as f32 / (levels.max(2) - 1) as f32;
+            let target = HATCH_LIGHTEST + t * (HATCH_DARKEST - HATCH_LIGHTEST);
+            while sum / n as f32 > target {
+                let mean = sum / n as f32;
+                let direction = if mean > 0.7 {
+                    (1, 0)
+                } else if mean > 0.45 {
+                    (0, 1)
+                } else {
+                    (1, 1)
+                };
+                //at least one pixel, or small tiles never get darker
+                let length = (((0.3 + 0.5 * hash(4 * stroke)) * size as f32) as i32).max(1);
+                let x0 = (hash(4 * stroke + 1) * size as f32) as i32;
+                let y0 = (hash(4 * stroke + 2) * size as f32) as i32;
+                //wraps around, the tile repeats seamlessly
+                for i in 0..length {
+                    let x = (x0 + i * direction.0).rem_euclid(size as i32);
+                    let y = (y0 + i * direction.1).rem_euclid(size as i32);
+                    let index = (y * size as i32 + x) as usize;
+                    sum -= paper[index] * HATCH_DARKNESS;
+                    paper[index] *= 1.0 - HATCH_DARKNESS;
+                }
+                stroke += 1;
+            }
+            textures.push(Texture::from_fn(size, size, |x, y| {
+                let value = paper[(y * size + x) as usize];
+                Vec3::new(value, value, value)
+            }));
+        }
+        Self::new(textures)
+    }
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    //tone: 1 is fully lit, returns the paper brightness blended between the two closest levels
+    pub fn sample(&self, tone: f32, texcoord: Vec2) -> f32 {
+        let texcoord = Vec2::new(
+            (texcoord.x * self.scale).rem_euclid(1.0),
+            (texcoord.y * self.scale).rem_euclid(1.0),
+        );
+        let position = (1.0 - tone.clamp(0.0, 1.0)) * (self.levels.len() - 1) as f32;
+        let (i, t) = (position.floor() as usize, position.fract());
+        let j = (i + 1).min(self.levels.len() - 1);
+        let a = self.levels[i].get_pixel(texcoord).x;
+        let b = self.levels[j].get_pixel(texcoord).x;
+        a + t * (b - a)
+    }
+}
+
+//cel shading, the light is banded by the ramp and the highlight has a hard edge
+#[derive(Clone, Debug)]
+pub struct ToonMaterial {
+    pub ramp: ToonRamp,
+    pub specular: Vec3,
+    pub specular_shininess: f32,
+    pub hatching: Option<TonalArtMap>, //replaces the ramp with strokes
+}
+impl ToonMaterial {
+    pub fn new(ramp: ToonRamp) -> Self {
+        Self {
+            ramp,
+            specular: Vec3::new(0.5, 0.5, 0.5),
+            specular_shininess: 32.0,
+            hatching: None,
+        }
+    }
+    pub fn with_specular(mut self, specular: Vec3, shininess: f32) -> Self {
+        self.specular = specular;
+        self.specular_shininess = shininess;
+        self
+    }
+    pub fn with_hatching(mut self, hatching: TonalArtMap) -> Self {
+        self.hatching = Some(hatching);
+        self
+    }
+}
+impl Default for ToonMaterial {
+    fn default() -> Self {
+        ToonMaterial::new(ToonRamp::Bands(3))
+    }
+}
+
+pub fn shade_toon(
+    material: &ToonMaterial,
+    surface: &Surface,
+    lights: &LightSet,
+    samples: impl Iterator<Item = LightSample>,
+    texcoord: Vec2,
+) -> Vec3 {
+    let (n, v) = (surface.normal, surface.view);
+    let mut diffuse = lights.get_ambient(n);
+    let mut specular = Vec3::zero();
+    for sample in samples {
+        let n_dot_l = n.dot(&sample.direction);
+        diffuse += sample.radiance.hadamard(&match &material.hatching {
+            //the strokes do the banding
+            Some(_) => {
+                let n_dot_l = n_dot_l.max(0.0);
+                Vec3::new(n_dot_l, n_dot_l, n_dot_l)
+            }
+            None => material.ramp.apply(n_dot_l),
+        });
+        let h = (v + sample.direction).normalize();
+        if n_dot_l > 0.0
+            && n.dot(&h).max(0.0).powf(material.specular_shininess) > SPECULAR_THRESHOLD
+        {
+            specular += sample.radiance.hadamard(&material.specular);
+        }
+    }
+
+    match &material.hatching {
+        Some(hatching) => {
+            let tone = (diffuse + specular).dot(&Vec3::new(0.2126, 0.7152, 0.0722));
+            hatching.sample(tone, texcoord) * surface.albedo
+        }
+        None => diffuse.hadamard(&surface.albedo) + specular,
+    }
+}
+
+impl Render {
+    //geometric outlines: silhouette edges between a front and a back facing triangle, open borders,
+    //and creases whose faces meet at more than `crease_angle`(radians)
+    pub fn draw_outline(
+        &mut self,
+        mesh: &Mesh,
+        model_mat: &Matrix4,
+        color: Color,
+        crease_angle: Option<f32>,
+    ) {
+        let vertexes = mesh.get_posed_vertexes();
+        let positions: Vec<Vec3> = vertexes
+            .iter()
+            .map(|vertex| {
+                model_mat
+                    .mul(vertex.position.upgrade())
+                    .perspective_divide()
+            })
+            .collect();
+        //vertexes are split at uv seams, weld them by position so the faces on both sides share edges
+        let mut welded = HashMap::new();
+        let ids: Vec<usize> = mesh
+            .vertexes
+            .iter()
+            .map(|vertex| {
+                let key = (
+                    vertex.position.x.to_bits(),
+                    vertex.position.y.to_bits(),
+                    vertex.position.z.to_bits(),
+                );
+                let id = welded.len();
+                *welded.entry(key).or_insert(id)
+            })
+            .collect();
+
+        let eye = self.camera.get_position();
+        let mut normals = vec![];
+        let mut front_facing = vec![];
+        let mut edges: HashMap<(usize, usize), (usize, usize, Vec<usize>)> = HashMap::new();
+        for (face, triangle) in mesh.indies.chunks_exact(3).enumerate() {
+            let (a, b, c) = (
+                positions[triangle[0]],
+                positions[triangle[1]],
+                positions[triangle[2]],
+            );
+            let normal = (b - a).cross(&(c - a));
+            front_facing.push(normal.dot(&(eye - a)) > 0.0);
+            normals.push(normal.normalize());
+            for (i, j) in [(0, 1), (1, 2), (2, 0)] {
+                let (start, end) = (triangle[i], triangle[j]);
+                let key = (ids[start].min(ids[end]), ids[start].max(ids[end]));
+                edges
+                    .entry(key)
+                    .or_insert_with(|| (start, end, vec![]))
+                    .2
+                    .push(face);
+            }
+        }
+
+        let crease_cos = crease_angle.map(|angle| angle.cos());
+        for (start, end, faces) in edges.values() {
+            let visible = match faces.as_slice() {
+                [_] => true,
+                [a, b] => {
+                    front_facing[*a] != front_facing[*b]
+                        || crease_cos.is_some_and(|cos| {
+                            (front_facing[*a] || front_facing[*b])
+                                && normals[*a].dot(&normals[*b]) < cos
+                        })
+                }
+                _ => false, //non-manifold
+            };
+            if visible {
+                //silhouettes are on grazing faces whose depth changes fast, pull them towards the camera
+                let pull = |p: Vec3| p + OUTLINE_OFFSET * (eye - p);
+                self.draw_line_3d(pull(positions[*start]), pull(positions[*end]), color, true);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::*;
+use std::ops::Mul;
+
+//scale, then rotate, then translate
+#[derive(Clone, Copy, Debug)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat, //unit
+    pub scale: Vec3,
+}
+impl Transform {
+    pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+    pub fn identity() -> Self {
+        Self::new(
+            Vec3::zero(),
+            Quat::new(1.0, 0.0, 0.0, 0.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        )
+    }
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::identity()
+        }
+    }
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::identity()
+        }
+    }
+    pub fn from_scale(scale: Vec3) -> Self {
+        Self {
+            scale,
+            ..Self::identity()
+        }
+    }
+
+    pub fn with_translation(mut self, translation: Vec3) -> Self {
+        self.translation = translation;
+        self
+    }
+    pub fn with_rotation(mut self, rotation: Quat) -> Self {
+        self.rotation = rotation;
+        self
+    }
+    pub fn with_scale(mut self, scale: Vec3) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    //T * R * S
+    pub fn to_matrix(&self) -> Matrix4 {
+        Matrix4::translate(self.translation)
+            * Matrix4::from_quat(self.rotation)
+            * Matrix4::scale(self.scale)
+    }
+    //decompose an affine matrix without shear, a mirroring is put into the x scale
+    pub fn from_matrix(matrix: &Matrix4) -> Self {
+        let column =
+            |col: usize| Vec3::new(matrix.get(0, col), matrix.get(1, col), matrix.get(2, col));
+        let (x_axis, y_axis, z_axis) = (column(0), column(1), column(2));
+        let mut scale = Vec3::new(
+            x_axis.length2().sqrt(),
+            y_axis.length2().sqrt(),
+            z_axis.length2().sqrt(),
+        );
+        if x_axis.cross(&y_axis).dot(&z_axis) < 0.0 {
+            scale.x = -scale.x;
+        }
+        let rotation = Quat::from_to_mat3(
+            (1.0 / scale.x) * x_axis,
+            (1.0 / scale.y) * y_axis,
+            (1.0 / scale.z) * z_axis,
+        );
+        Self::new(column(3), rotation, scale)
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.transform_vector(point) + self.translation
+    }
+    //directions and offsets, no translation
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotate(vector.hadamard(&self.scale))
+    }
+    //inverse transpose of R * S is R * S^-1, normalized
+    pub fn transform_normal(&self, normal: Vec3) -> Vec3 {
+        let scaled = Vec3::new(
+            normal.x / self.scale.x,
+            normal.y / self.scale.y,
+            normal.z / self.scale.z,
+        );
+        self.rotate(scaled).normalize()
+    }
+    fn rotate(&self, vector: Vec3) -> Vec3 {
+        let rotated =
+            Matrix4::from_quat(self.rotation).mul(Vec4::new(vector.x, vector.y, vector.z, 0.0));
+        Vec3::new(rotated.x, rotated.y, rotated.z)
+    }
+
+    //exact for uniform scale, a non-uniform scale under a rotation has no TRS inverse,
+    //use `to_matrix().inv()` then
+    pub fn inverse(&self) -> Self {
+        let rotation = self.rotation.inv();
+        let scale = Vec3::new(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        let inverse = Self::new(Vec3::zero(), rotation, scale);
+        Self {
+            translation: -inverse.transform_vector(self.translation),
+            ..inverse
+        }
+    }
+}
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+//parent * child, the child is applied first. like `inverse` the scale is exact when it is uniform
+impl Mul<Transform> for Transform {
+    type Output = Transform;
+    fn mul(self, rhs: Transform) -> Self::Output {
+        Transform::new(
+            self.transform_point(rhs.translation),
+            self.rotation * rhs.rotation,
+            self.scale.hadamard(&rhs.scale),
+        )
+    }
+}
+impl From<Transform> for Matrix4 {
+    fn from(transform: Transform) -> Self {
+        transform.to_matrix()
+    }
+}
@@ -0,0 +1,150 @@
+use raster::*;
+
+fn assert_near(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-5, "{a} != {b}");
+}
+
+//q and -q are the same rotation
+fn assert_same_rotation(a: Quat, b: Quat) {
+    assert!(a.dot(&b).abs() > 1.0 - 1e-5, "{a:?} != {b:?}");
+}
+
+fn track(interpolation: Interpolation) -> Track<f32> {
+    Track::new(interpolation)
+        .with_key(1.0, 2.0)
+        .with_key(0.0, 0.0)
+        .with_key(2.0, 3.0)
+}
+
+#[test]
+fn track_sample() {
+    assert!(Track::<f32>::new(Interpolation::Linear)
+        .sample(0.0)
+        .is_none());
+
+    let step = track(Interpolation::Step);
+    assert_near(step.sample(-1.0).unwrap(), 0.0);
+    assert_near(step.sample(0.5).unwrap(), 0.0);
+    assert_near(step.sample(1.0).unwrap(), 2.0);
+    assert_near(step.sample(5.0).unwrap(), 3.0);
+
+    let linear = track(Interpolation::Linear);
+    assert_near(linear.sample(0.5).unwrap(), 1.0);
+    assert_near(linear.sample(1.5).unwrap(), 2.5);
+
+    //flat tangents ease in and out of every key
+    let cubic = track(Interpolation::CubicSpline);
+    assert_near(cubic.sample(1.0).unwrap(), 2.0);
+    assert_near(cubic.sample(0.5).unwrap(), 1.0);
+    assert_near(cubic.sample(0.25).unwrap(), 2.0 * 0.15625);
+    assert_near(cubic.get_duration(), 2.0);
+}
+
+#[test]
+fn smooth_tangents() {
+    //a straight line stays straight
+    let line = Track::new(Interpolation::CubicSpline)
+        .with_key(0.0, 0.0)
+        .with_key(1.0, 2.0)
+        .with_key(3.0, 6.0)
+        .with_smooth_tangents();
+    for key in line.get_keyframes() {
+        assert_near(key.in_tangent, 2.0);
+        assert_near(key.out_tangent, 2.0);
+    }
+    for time in [0.25, 0.5, 1.7, 2.9] {
+        assert_near(line.sample(time).unwrap(), 2.0 * time);
+    }
+
+    //the middle key takes the slope between its neighbours
+    let curve = track(Interpolation::CubicSpline).with_smooth_tangents();
+    let keys = curve.get_keyframes();
+    assert_near(keys[0].out_tangent, 2.0);
+    assert_near(keys[1].in_tangent, 1.5);
+    assert_near(keys[2].in_tangent, 1.0);
+}
+
+#[test]
+fn quaternion_keys_in_both_hemispheres() {
+    let rotation = |angle: f32| Quat::from_axis_angle(Vec3::Y, angle);
+    for smooth in [false, true] {
+        let mut track = Track::new(Interpolation::CubicSpline)
+            .with_key(0.0, rotation(0.0))
+            .with_key(1.0, -rotation(0.2))
+            .with_key(2.0, rotation(0.4));
+        if smooth {
+            track = track.with_smooth_tangents();
+        }
+        for time in [0.5, 1.5] {
+            assert_same_rotation(track.sample(time).unwrap(), rotation(time * 0.2));
+        }
+    }
+}
+
+#[test]
+fn playback_modes() {
+    let mut looped = Playback::new(2.0).with_mode(PlaybackMode::Loop);
+    looped.advance(5.0);
+    assert_near(looped.get_time(), 1.0);
+    assert!(looped.playing);
+
+    let mut ping_pong = Playback::new(2.0).with_mode(PlaybackMode::PingPong);
+    ping_pong.advance(1.5);
+    assert_near(ping_pong.get_time(), 1.5);
+    ping_pong.advance(1.0);
+    assert_near(ping_pong.get_time(), 1.5);
+    ping_pong.advance(2.0);
+    assert_near(ping_pong.get_time(), 0.5);
+
+    let mut once = Playback::new(2.0);
+    once.advance(1.0);
+    assert_near(once.get_time(), 1.0);
+    once.advance(3.0);
+    assert_near(once.get_time(), 2.0);
+    assert!(!once.playing);
+}
+
+#[test]
+fn playback_backwards() {
+    let mut looped = Playback::new(2.0)
+        .with_mode(PlaybackMode::Loop)
+        .with_speed(-1.0);
+    looped.advance(0.5);
+    assert_near(looped.get_time(), 1.5);
+
+    let mut ping_pong = Playback::new(2.0)
+        .with_mode(PlaybackMode::PingPong)
+        .with_speed(-2.0);
+    ping_pong.advance(0.25);
+    assert_near(ping_pong.get_time(), 0.5);
+
+    let mut once = Playback::new(2.0).with_speed(-1.0);
+    once.seek(2.0);
+    once.advance(0.5);
+    assert_near(once.get_time(), 1.5);
+    assert!(once.playing);
+    once.advance(2.0);
+    assert_near(once.get_time(), 0.0);
+    assert!(!once.playing);
+}
+
+#[test]
+fn frames_without_a_placeholder_are_numbered() {
+    let dir = std::env::temp_dir().join(format!("raster_sequence_{}", std::process::id()));
+    let camera = Camera::new(
+        Frustum::new(0.1, 10.0, 60f32.to_radians(), 1.0),
+        Vec3::new(0.0, 0.0, 2.0),
+        Vec3::zero(),
+    );
+    let mut render = Render::new(4, 4, camera, Shader::standard());
+    let mut scene = Scene::new(LightSet::new(Vec3::new(0.2, 0.2, 0.2)));
+    let clip = AnimationClip::new("empty");
+    let path = dir.join("frame.png").to_string_lossy().to_string();
+    render.render_sequence(&mut scene, &clip, &mut Playback::new(1.0), 2, 2.0, &path);
+
+    for name in ["frame_0000.png", "frame_0001.png"] {
+        assert!(dir.join(name).exists(), "{name}");
+    }
+    assert!(!dir.join("frame.png").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,106 @@
+use raster::*;
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length2() < 1e-8, "{a:?} != {b:?}");
+}
+
+fn assert_quat_close(a: Quat, b: Quat) {
+    let difference = [a.x - b.x, a.y - b.y, a.z - b.z, a.w - b.w];
+    assert!(difference.iter().all(|d| d.abs() < 1e-5), "{a:?} != {b:?}");
+}
+
+fn key(time: f32, x: f32, angle: f32, fov: f32, projection: Projection) -> CameraKey {
+    CameraKey {
+        time,
+        position: Vec3::new(x, 1.0, 5.0),
+        rotation: Quat::from_axis_angle(Vec3::Y, angle),
+        fov,
+        projection,
+    }
+}
+
+//evenly spaced keys on a line, the spline keeps to it
+fn path() -> CameraPath {
+    let mut path = CameraPath::new();
+    for (i, projection) in [
+        Projection::Orthographic { height: 2.0 },
+        Projection::Orthographic { height: 4.0 },
+        Projection::Perspective,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let i = i as f32;
+        path.add_key(key(i, i * 2.0, i * 0.5, 0.8 + i * 0.2, projection));
+    }
+    path
+}
+
+#[test]
+fn display_and_parse_round_trip() {
+    let path = path();
+    let parsed = CameraPath::parse(&path.to_string()).unwrap();
+    assert_eq!(parsed.get_keys().len(), path.get_keys().len());
+    for (parsed, key) in parsed.get_keys().iter().zip(path.get_keys()) {
+        assert_eq!(parsed.time, key.time);
+        assert_close(parsed.position, key.position);
+        assert_quat_close(parsed.rotation, key.rotation);
+        assert_eq!(parsed.fov, key.fov);
+        assert_eq!(parsed.projection, key.projection);
+    }
+}
+
+#[test]
+fn parse_errors_give_the_line() {
+    let error = CameraPath::parse("# keys\n0 0 0 0 1 0 0 0 1\n1 0 0\n").unwrap_err();
+    assert!(error.starts_with("line 3:"), "{error}");
+    let error = CameraPath::parse("0 0 0 0 1 0 0 x 1\n").unwrap_err();
+    assert!(error.starts_with("line 1:"), "{error}");
+}
+
+#[test]
+fn sample_at_and_between_keys() {
+    let path = path();
+    for key in path.get_keys() {
+        let sample = path.sample(key.time).unwrap();
+        assert_close(sample.position, key.position);
+        assert_quat_close(sample.rotation, key.rotation);
+        assert!((sample.fov - key.fov).abs() < 1e-6);
+        assert_eq!(sample.projection, key.projection);
+    }
+
+    let sample = path.sample(0.5).unwrap();
+    assert_close(sample.position, Vec3::new(1.0, 1.0, 5.0));
+    assert_quat_close(sample.rotation, Quat::from_axis_angle(Vec3::Y, 0.25));
+    assert!((sample.fov - 0.9).abs() < 1e-6);
+    assert_eq!(sample.projection, Projection::Orthographic { height: 3.0 });
+    //the projection changes at the key
+    let sample = path.sample(1.5).unwrap();
+    assert_eq!(sample.projection, Projection::Orthographic { height: 4.0 });
+
+    //clamped outside of the keys
+    assert_close(
+        path.sample(-1.0).unwrap().position,
+        Vec3::new(0.0, 1.0, 5.0),
+    );
+    assert_close(path.sample(3.0).unwrap().position, Vec3::new(4.0, 1.0, 5.0));
+    assert!(CameraPath::new().sample(0.0).is_none());
+}
+
+#[test]
+fn apply_sets_the_projection() {
+    let mut camera = Camera::new(
+        Frustum::new(0.1, 10.0, 1.0, 1.0),
+        Vec3::zero(),
+        Vec3::new(0.0, 0.0, -1.0),
+    );
+    path().apply(0.0, &mut camera);
+    assert_eq!(
+        camera.get_projection(),
+        Projection::Orthographic { height: 2.0 }
+    );
+    assert_close(camera.get_position(), Vec3::new(0.0, 1.0, 5.0));
+    path().apply(2.0, &mut camera);
+    assert_eq!(camera.get_projection(), Projection::Perspective);
+    assert!((camera.get_frustum().get_fov() - 1.2).abs() < 1e-6);
+}
@@ -0,0 +1,114 @@
+use raster::*;
+
+const PROJECTIONS: [Projection; 2] = [
+    Projection::Perspective,
+    Projection::Orthographic { height: 4.0 },
+];
+
+fn camera(projection: Projection) -> Camera {
+    Camera::new(
+        Frustum::new(0.1, 20.0, 60f32.to_radians(), 1.0),
+        Vec3::new(0.0, 0.0, 5.0),
+        Vec3::zero(),
+    )
+    .with_projection(projection)
+}
+
+fn cube(center: Vec3) -> Aabb {
+    let half = Vec3::new(0.5, 0.5, 0.5);
+    Aabb::new(center - half, center + half)
+}
+
+fn quad() -> Mesh {
+    let vertexes = [[-0.5, -0.5], [0.5, -0.5], [0.5, 0.5], [-0.5, 0.5]]
+        .iter()
+        .map(|[x, y]| Vertex::new(Vec3::new(*x, *y, 0.0), Vec3::Z, Vec2::default()))
+        .collect();
+    let material = Material::new(
+        PhongMaterial::default(),
+        BaseColor::Color([255, 255, 255]),
+        None,
+        None,
+    );
+    Mesh::new(vertexes, vec![0, 1, 2, 0, 2, 3], material)
+}
+
+#[test]
+fn planes_of_both_projections() {
+    for projection in PROJECTIONS {
+        let planes = camera(projection).get_frustum_planes();
+        assert!(planes.contains(Vec3::zero()), "{projection:?}");
+        assert!(!planes.contains(Vec3::new(0.0, 0.0, 6.0)), "{projection:?}");
+
+        assert!(
+            planes.intersects_aabb(&cube(Vec3::zero())),
+            "{projection:?}"
+        );
+        //through the near plane
+        assert!(planes.intersects_aabb(&cube(Vec3::new(0.0, 0.0, 5.0))));
+        //behind the camera
+        assert!(!planes.intersects_aabb(&cube(Vec3::new(0.0, 0.0, 7.0))));
+        //left of the view
+        assert!(!planes.intersects_aabb(&cube(Vec3::new(-10.0, 0.0, 0.0))));
+        //beyond the far plane at z = -15
+        assert!(!planes.intersects_aabb(&cube(Vec3::new(0.0, 0.0, -16.0))));
+        assert!(planes.intersects_aabb(&cube(Vec3::new(0.0, 0.0, -15.0))));
+    }
+}
+
+#[test]
+fn planes_of_a_small_model_scale() {
+    let mut camera = camera(Projection::Perspective);
+    let view_projection = camera.get_projection_matrix() * camera.get_view_matrix();
+    let scale = Matrix4::scale(Vec3::new(0.001, 0.001, 0.001));
+    let inside = FrustumPlanes::from_matrix(&(view_projection * scale));
+    assert!(inside.intersects_aabb(&cube(Vec3::zero())));
+    let left = Matrix4::translate(Vec3::new(-10.0, 0.0, 0.0)) * scale;
+    let outside = FrustumPlanes::from_matrix(&(view_projection * left));
+    assert!(!outside.intersects_aabb(&cube(Vec3::zero())));
+}
+
+#[test]
+fn culled_meshes_are_counted() {
+    let lights = LightSet::new(Vec3::new(0.2, 0.2, 0.2));
+    for projection in PROJECTIONS {
+        let mut render = Render::new(32, 32, camera(projection), Shader::standard());
+        render.reset();
+        let shown = render.draw(&quad(), &lights, Matrix4::ident());
+        assert_eq!(shown.meshes_culled, 0);
+        assert_eq!(shown.vertices_shaded, 4);
+
+        let left = Matrix4::translate(Vec3::new(-10.0, 0.0, 0.0));
+        let culled = render.draw(&quad(), &lights, left);
+        assert_eq!(culled.meshes_culled, 1);
+        assert_eq!(culled.vertices_shaded, 0);
+
+        render.set_frustum_culling(false);
+        let drawn = render.draw(&quad(), &lights, left);
+        assert_eq!(drawn.meshes_culled, 0);
+        assert_eq!(drawn.vertices_shaded, 4);
+    }
+}
+
+#[test]
+fn culled_nodes_skip_hidden_children() {
+    let mut scene = Scene::new(LightSet::new(Vec3::new(0.2, 0.2, 0.2)));
+    let mesh = scene.add_mesh(quad());
+    scene.add_node(Node::new("front").with_mesh(mesh), None);
+    let left = scene.add_node(
+        Node::new("left")
+            .with_translation(Vec3::new(-10.0, 0.0, 0.0))
+            .with_mesh(mesh),
+        None,
+    );
+    scene.add_node(Node::new("child").with_mesh(mesh), Some(left));
+    let mut hidden = Node::new("hidden").with_mesh(mesh);
+    hidden.visible = false;
+    scene.add_node(hidden, Some(left));
+
+    let mut render = Render::new(32, 32, camera(Projection::Perspective), Shader::standard());
+    render.reset();
+    let stats = render.draw_scene(&scene);
+    assert_eq!(stats.nodes_culled, 2);
+    assert_eq!(stats.vertices_shaded, 4);
+}
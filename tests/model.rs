@@ -0,0 +1,148 @@
+use raster::*;
+
+fn write(name: &str, bytes: &[u8]) -> String {
+    let dir = std::env::temp_dir().join(format!("raster_model_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, bytes).unwrap();
+    path.to_string_lossy().to_string()
+}
+
+fn assert_close(a: Vec4, b: Vec4) {
+    let difference = a + -1.0 * b;
+    let length2 = [difference.x, difference.y, difference.z, difference.w]
+        .iter()
+        .map(|d| d * d)
+        .sum::<f32>();
+    assert!(length2 < 1e-10, "{a:?} != {b:?}");
+}
+
+const PLY_HEADER: &str = "element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+";
+
+const POSITIONS: [[f32; 3]; 4] = [
+    [0.0, 0.0, 0.0],
+    [1.0, 0.0, 0.0],
+    [1.0, 1.0, 0.0],
+    [0.0, 1.0, 0.0],
+];
+const COLORS: [[u8; 3]; 4] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+
+//one quad, split into two triangles, normals from the faces
+fn assert_quad(vertexes: &[Vertex], indies: &[usize]) {
+    assert_eq!(indies, [0, 1, 2, 0, 2, 3]);
+    assert_eq!(vertexes.len(), 4);
+    for ((vertex, position), color) in vertexes.iter().zip(POSITIONS).zip(COLORS) {
+        assert!((vertex.position - Vec3::from(position)).length2() < 1e-10);
+        assert!((vertex.normal - Vec3::Z).length2() < 1e-10);
+        let [r, g, b] = color.map(|c| c as f32 / 255.0);
+        assert_close(vertex.color, Vec4::new(r, g, b, 1.0));
+    }
+}
+
+#[test]
+fn ply_ascii() {
+    let mut source = format!("ply\nformat ascii 1.0\ncomment quad\n{PLY_HEADER}");
+    for (position, color) in POSITIONS.iter().zip(COLORS) {
+        source += &format!(
+            "{} {} {} {} {} {}\n",
+            position[0], position[1], position[2], color[0], color[1], color[2]
+        );
+    }
+    source += "4 0 1 2 3\n";
+    let (vertexes, indies) = load_model(&write("ascii.ply", source.as_bytes()));
+    assert_quad(&vertexes, &indies);
+}
+
+#[test]
+fn ply_binary() {
+    for (format, big_endian) in [("binary_little_endian", false), ("binary_big_endian", true)] {
+        let mut bytes = format!("ply\nformat {format} 1.0\n{PLY_HEADER}").into_bytes();
+        for (position, color) in POSITIONS.iter().zip(COLORS) {
+            for value in position {
+                bytes.extend(match big_endian {
+                    true => value.to_be_bytes(),
+                    false => value.to_le_bytes(),
+                });
+            }
+            bytes.extend(color);
+        }
+        bytes.push(4);
+        for index in 0..4i32 {
+            bytes.extend(match big_endian {
+                true => index.to_be_bytes(),
+                false => index.to_le_bytes(),
+            });
+        }
+        let (vertexes, indies) = load_model(&write(&format!("{format}.ply"), &bytes));
+        assert_quad(&vertexes, &indies);
+    }
+}
+
+#[test]
+fn ply_float_colors_are_linear() {
+    let source = "ply
+format ascii 1.0
+element vertex 3
+property float x
+property float y
+property float z
+property float nx
+property float ny
+property float nz
+property float red
+property float green
+property float blue
+property float alpha
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 0 0 1 0.5 0.25 0 0.5
+1 0 0 0 0 1 0.5 0.25 0 0.5
+0 1 0 0 0 1 0.5 0.25 0 0.5
+3 0 1 2
+";
+    let (vertexes, indies) = load_model(&write("float.ply", source.as_bytes()));
+    assert_eq!(indies, [0, 1, 2]);
+    for vertex in vertexes {
+        assert!((vertex.normal - Vec3::Z).length2() < 1e-10);
+        assert_close(vertex.color, Vec4::new(0.5, 0.25, 0.0, 0.5));
+    }
+}
+
+//the two vertexes at the origin have their own colours
+#[test]
+fn obj_colors_follow_the_v_lines() {
+    let source = "v 0 0 0 1 0 0
+v 1 0 0 0 1 0
+v 0 1 0 0 0 1
+v 0 0 0 0 1 1
+v 0 1 0
+vn 0 0 1
+vt 0 0
+f 1/1/1 2/1/1 3/1/1
+f 4/1/1 2/1/1 5/1/1
+";
+    let (vertexes, indies) = load_model(&write("colors.obj", source.as_bytes()));
+    let colors: Vec<Vec4> = indies.iter().map(|index| vertexes[*index].color).collect();
+    let red = Vec4::new(1.0, 0.0, 0.0, 1.0);
+    let green = Vec4::new(0.0, 1.0, 0.0, 1.0);
+    let blue = Vec4::new(0.0, 0.0, 1.0, 1.0);
+    let cyan = Vec4::new(0.0, 1.0, 1.0, 1.0);
+    let white = Vec4::new(1.0, 1.0, 1.0, 1.0);
+    for (color, expected) in colors
+        .into_iter()
+        .zip([red, green, blue, cyan, green, white])
+    {
+        assert_close(color, expected);
+    }
+}
@@ -0,0 +1,155 @@
+use raster::*;
+
+fn material() -> Material {
+    Material::new(
+        PhongMaterial::default(),
+        BaseColor::Color([255, 255, 255]),
+        None,
+        None,
+    )
+}
+
+fn quad(positions: [[f32; 3]; 4], normal: Vec3) -> Vec<Vertex> {
+    positions
+        .iter()
+        .map(|position| Vertex::new(Vec3::from(*position), normal, Vec2::default()))
+        .collect()
+}
+
+fn assert_shape(vertexes: &[Vertex], shape: &[Vertex]) {
+    assert_eq!(vertexes.len(), shape.len());
+    for (vertex, expected) in vertexes.iter().zip(shape) {
+        let bits = |v: Vec3| [v.x, v.y, v.z].map(f32::to_bits);
+        assert_eq!(bits(vertex.position), bits(expected.position));
+        assert_eq!(bits(vertex.normal), bits(expected.normal));
+    }
+}
+
+#[test]
+fn weights_zero_and_one_give_the_base_and_target() {
+    let base = quad(
+        [
+            [-0.3, -0.7, 0.1],
+            [0.9, -0.2, 0.0],
+            [1.1, 1.3, -0.4],
+            [-0.6, 0.8, 0.25],
+        ],
+        Vec3::Z,
+    );
+    let smile = quad(
+        [
+            [-0.1, -0.9, 0.3],
+            [0.7, -0.2, 0.6],
+            [1.7, 1.1, -0.2],
+            [-0.6, 0.8, 0.25],
+        ],
+        Vec3::new(0.6, 0.0, 0.8),
+    );
+    let frown = quad(
+        [
+            [-0.3, -0.5, 0.1],
+            [0.9, -0.4, 0.0],
+            [1.1, 1.3, -0.9],
+            [-0.2, 0.8, 0.25],
+        ],
+        Vec3::Y,
+    );
+    let mut mesh = Mesh::new(base.clone(), vec![0, 1, 2, 0, 2, 3], material())
+        .with_morph_target(MorphTarget::from_shapes("smile", &base, &smile))
+        .with_morph_target(MorphTarget::from_shapes("frown", &base, &frown));
+
+    assert_shape(&mesh.get_posed_vertexes(), &base);
+
+    let smile_target = mesh.find_morph_target("smile").unwrap();
+    mesh.set_morph_weight(smile_target, 1.0);
+    assert_shape(&mesh.get_posed_vertexes(), &smile);
+
+    mesh.set_morph_weight(smile_target, 0.0);
+    mesh.set_morph_weight(mesh.find_morph_target("frown").unwrap(), 1.0);
+    assert_shape(&mesh.get_posed_vertexes(), &frown);
+
+    mesh.set_morph_weight(mesh.find_morph_target("frown").unwrap(), 0.0);
+    assert_shape(&mesh.get_posed_vertexes(), &base);
+}
+
+//targets of two meshes merged into one only move their own vertexes
+#[test]
+fn targets_cover_a_part_of_the_mesh() {
+    let left = quad(
+        [
+            [-2.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0],
+            [-1.0, 1.0, 0.0],
+            [-2.0, 1.0, 0.0],
+        ],
+        Vec3::Z,
+    );
+    let right = quad(
+        [
+            [1.0, 0.0, 0.0],
+            [2.0, 0.0, 0.0],
+            [2.0, 1.0, 0.0],
+            [1.0, 1.0, 0.0],
+        ],
+        Vec3::Z,
+    );
+    let raised = quad(
+        [
+            [1.0, 0.0, 0.7],
+            [2.0, 0.0, 0.3],
+            [2.0, 1.3, 0.0],
+            [1.0, 1.0, 0.1],
+        ],
+        Vec3::new(0.6, 0.0, 0.8),
+    );
+    let base = [left.clone(), right.clone()].concat();
+    let mut mesh = Mesh::new(base.clone(), vec![0, 1, 2, 4, 5, 6], material())
+        .with_morph_target(MorphTarget::from_shapes("left", &left, &raised))
+        .with_morph_target(MorphTarget::from_shapes("right", &right, &raised).with_start(4));
+
+    mesh.set_morph_weight(1, 1.0);
+    assert_shape(
+        &mesh.get_posed_vertexes(),
+        &[left.clone(), raised.clone()].concat(),
+    );
+    mesh.set_morph_weight(0, 1.0);
+    assert_shape(
+        &mesh.get_posed_vertexes(),
+        &[raised.clone(), raised].concat(),
+    );
+    mesh.set_morph_weight(0, 0.0);
+    mesh.set_morph_weight(1, 0.0);
+    assert_shape(&mesh.get_posed_vertexes(), &base);
+}
+
+#[test]
+fn obj_sequence() {
+    let dir = std::env::temp_dir().join(format!("raster_morph_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let write = |name: &str, top: f32| {
+        let path = dir.join(name);
+        std::fs::write(
+            &path,
+            format!(
+                "v 0 0 0\nv 1 0 0\nv 1 {top} 0\nv 0 {top} 0\nvn 0 0 1\nvt 0 0\nf 1/1/1 2/1/1 3/1/1\nf 1/1/1 3/1/1 4/1/1\n"
+            ),
+        )
+        .unwrap();
+        path.to_string_lossy().to_string()
+    };
+    let base_path = write("base.obj", 1.0);
+    let tall_path = write("tall.obj", 2.5);
+
+    let (vertexes, indies, targets) = load_morph_sequence(&[&base_path, &tall_path]);
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].name, "tall");
+    let mut mesh = Mesh::new(vertexes, indies, material()).with_morph_target(targets[0].clone());
+
+    let (base, _) = load_model(&base_path);
+    let (tall, _) = load_model(&tall_path);
+    assert_shape(&mesh.get_posed_vertexes(), &base);
+    mesh.set_morph_weight(0, 1.0);
+    assert_shape(&mesh.get_posed_vertexes(), &tall);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,51 @@
+use raster::*;
+
+const IDENTITY: &str = "0 0 0\n1 0 0\n0 1 0\n1 1 0\n0 0 1\n1 0 1\n0 1 1\n1 1 1\n";
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length2() < 1e-10, "{a:?} != {b:?}");
+}
+
+#[test]
+fn cube_keywords() {
+    let lut = ColorLut::parse_cube(&format!(
+        "# graded\nTITLE \"identity\"\nLUT_3D_SIZE 2\nLUT_3D_INPUT_RANGE 0.0 2.0\nLUT_IN_VIDEO_RANGE\n\n{IDENTITY}"
+    ));
+    assert_close(
+        lut.sample(Vec3::new(2.0, 2.0, 2.0)),
+        Vec3::new(1.0, 1.0, 1.0),
+    );
+    assert_close(
+        lut.sample(Vec3::new(1.0, 0.0, 0.5)),
+        Vec3::new(0.5, 0.0, 0.25),
+    );
+}
+
+//the lut gives srgb 0.5 everywhere, written like any other linear colour of the frame
+#[test]
+fn post_stack_keeps_the_encoding_of_the_frame() {
+    let gray = "0.5 0.5 0.5\n".repeat(8);
+    for srgb in [true, false] {
+        let camera = Camera::new(
+            Frustum::new(0.1, 10.0, 60f32.to_radians(), 1.0),
+            Vec3::new(0.0, 0.0, 2.0),
+            Vec3::zero(),
+        );
+        let output_transform = OutputTransform::default().with_srgb(srgb);
+        let mut render = Render::new(4, 4, camera, Shader::standard());
+        render.set_output_transform(output_transform);
+        render.set_background([100, 50, 200]);
+        render.set_post_stack(PostStack::new().with(PostEffect::ColorGrading(
+            ColorLut::parse_cube(&format!("LUT_3D_SIZE 2\n{gray}")),
+        )));
+        render.reset();
+        render.resolve();
+
+        let linear = srgb_to_linear(0.5);
+        let expected = output_transform.encode(Vec3::new(linear, linear, linear), 0, 0);
+        assert!(
+            render.get_frame().chunks(3).all(|pixel| pixel == expected),
+            "srgb {srgb}"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+use raster::*;
+
+fn render(projection: Projection) -> Render {
+    let camera = Camera::new(
+        Frustum::new(0.1, 100.0, 60f32.to_radians(), 1.0),
+        Vec3::new(0.0, 0.0, 5.0),
+        Vec3::zero(),
+    )
+    .with_projection(projection);
+    let mut render = Render::new(64, 64, camera, Shader::standard());
+    render.reset();
+    render
+}
+
+fn lit_pixels(render: &Render) -> usize {
+    render
+        .get_frame()
+        .chunks(3)
+        .filter(|pixel| pixel.iter().any(|&c| c != 0))
+        .count()
+}
+
+#[test]
+fn line_is_drawn_in_both_projections() {
+    for projection in [
+        Projection::Perspective,
+        Projection::Orthographic { height: 4.0 },
+    ] {
+        let mut render = render(projection);
+        render.draw_line_3d(
+            Vec3::new(-1.0, 0.0, 0.0),
+            Vec3::new(1.0, 0.0, 0.0),
+            [255, 255, 255],
+            false,
+        );
+        assert!(lit_pixels(&render) > 10, "no line with {projection:?}");
+    }
+}
+
+#[test]
+fn project_in_front_of_the_camera() {
+    for projection in [
+        Projection::Perspective,
+        Projection::Orthographic { height: 4.0 },
+    ] {
+        let mut render = render(projection);
+        let center = render.project(Vec3::zero()).unwrap();
+        assert!((center.x - 32.0).abs() < 1e-3 && (center.y - 32.0).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn big_orthographic_volume_is_inverted() {
+    let camera = Camera::new(
+        Frustum::new(0.1, 1000.0, 60f32.to_radians(), 1.0),
+        Vec3::new(0.0, 0.0, 5.0),
+        Vec3::zero(),
+    )
+    .with_projection(Projection::Orthographic { height: 100.0 });
+    assert!(camera.get_projection_matrix().inv().is_some());
+    let mut render = Render::new(64, 64, camera, Shader::standard());
+    render.set_post_stack(PostStack::new().with(PostEffect::Ssao {
+        radius: 0.5,
+        intensity: 1.0,
+        samples: 8,
+    }));
+    render.reset();
+    render.resolve();
+
+    let scale = Matrix4::scale(Vec3::new(0.005, 0.005, 0.005));
+    assert!(scale.inv().is_some());
+    assert!(Matrix4::scale(Vec3::new(1.0, 1.0, 0.0)).inv().is_none());
+}
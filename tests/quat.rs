@@ -0,0 +1,135 @@
+use proptest::prelude::*;
+use raster::*;
+use std::f32::consts::PI;
+
+const ORDERS: [EulerOrder; 6] = [
+    EulerOrder::XYZ,
+    EulerOrder::XZY,
+    EulerOrder::YXZ,
+    EulerOrder::YZX,
+    EulerOrder::ZXY,
+    EulerOrder::ZYX,
+];
+
+fn vec3() -> impl Strategy<Value = Vec3> {
+    (-1.0f32..1.0, -1.0f32..1.0, -1.0f32..1.0)
+        .prop_filter("zero vector", |(x, y, z)| x * x + y * y + z * z > 0.01)
+        .prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn unit_quat() -> impl Strategy<Value = Quat> {
+    (-1.0f32..1.0, -1.0f32..1.0, -1.0f32..1.0, -1.0f32..1.0)
+        .prop_filter("zero quat", |(x, y, z, w)| {
+            x * x + y * y + z * z + w * w > 0.01
+        })
+        .prop_map(|(x, y, z, w)| Quat::new(x, y, z, w).normalize())
+}
+
+//q and -q are the same rotation
+fn same_rotation(a: Quat, b: Quat) -> bool {
+    a.dot(&b).abs() > 1.0 - 1e-4
+}
+
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).length2() < 1e-6
+}
+
+proptest! {
+    #[test]
+    fn axis_angle_round_trip(axis in vec3(), angle in 0.01f32..PI - 0.01) {
+        let (back_axis, back_angle) = Quat::from_axis_angle(axis, angle).to_axis_angle();
+        prop_assert!((back_angle - angle).abs() < 1e-3);
+        prop_assert!(close(back_axis, axis.normalize()));
+    }
+
+    #[test]
+    fn matrix_round_trip(q in unit_quat()) {
+        prop_assert!(same_rotation(Quat::from_mat4(&q.get_rotation()), q));
+    }
+
+    //half turns only have the diagonal branches
+    #[test]
+    fn matrix_round_trip_half_turn(axis in vec3()) {
+        let q = Quat::from_axis_angle(axis, PI);
+        prop_assert!(same_rotation(Quat::from_mat4(&q.get_rotation()), q));
+    }
+
+    #[test]
+    fn euler_round_trip(q in unit_quat(), order in 0usize..6) {
+        let angles = q.to_euler(ORDERS[order]);
+        prop_assert!(same_rotation(Quat::from_euler(ORDERS[order], angles), q));
+    }
+
+    #[test]
+    fn euler_angles_recovered(
+        a in -PI + 0.01..PI - 0.01,
+        b in -PI / 2.0 + 0.01..PI / 2.0 - 0.01,
+        c in -PI + 0.01..PI - 0.01,
+        order in 0usize..6,
+    ) {
+        //b is the middle rotation of the order
+        let mut angles = [0.0; 3];
+        let axes = match ORDERS[order] {
+            EulerOrder::XYZ => [0, 1, 2],
+            EulerOrder::XZY => [0, 2, 1],
+            EulerOrder::YXZ => [1, 0, 2],
+            EulerOrder::YZX => [1, 2, 0],
+            EulerOrder::ZXY => [2, 0, 1],
+            EulerOrder::ZYX => [2, 1, 0],
+        };
+        angles[axes[0]] = a;
+        angles[axes[1]] = b;
+        angles[axes[2]] = c;
+        let angles = Vec3::new(angles[0], angles[1], angles[2]);
+        let back = Quat::from_euler(ORDERS[order], angles).to_euler(ORDERS[order]);
+        prop_assert!((back - angles).length2() < 1e-4);
+    }
+
+    #[test]
+    fn euler_gimbal_lock(a in -PI + 0.01..PI - 0.01, c in -PI + 0.01..PI - 0.01, sign in prop::bool::ANY) {
+        let b = if sign { PI / 2.0 } else { -PI / 2.0 };
+        let q = Quat::from_euler(EulerOrder::XYZ, Vec3::new(a, b, c));
+        let angles = q.to_euler(EulerOrder::XYZ);
+        prop_assert!(same_rotation(Quat::from_euler(EulerOrder::XYZ, angles), q));
+    }
+
+    #[test]
+    fn rotate_matches_matrix(q in unit_quat(), v in vec3()) {
+        let m = q.get_rotation().mul(Vec4::new(v.x, v.y, v.z, 0.0));
+        prop_assert!(close(q.rotate(v), Vec3::new(m.x, m.y, m.z)));
+    }
+
+    #[test]
+    fn inverse(q in unit_quat(), scale in 0.1f32..10.0) {
+        let q = Quat::new(q.x * scale, q.y * scale, q.z * scale, q.w * scale);
+        prop_assert!(same_rotation(q * q.inv(), Quat::identity()));
+        prop_assert!(((q * q.inv()).x - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rotation_arc(from in vec3(), to in vec3()) {
+        let q = Quat::from_rotation_arc(from, to);
+        prop_assert!(close(q.rotate(from.normalize()), to.normalize()));
+        prop_assert!(close(Quat::from_rotation_arc(from, -from).rotate(from.normalize()), -from.normalize()));
+    }
+
+    #[test]
+    fn slerp_constant_speed(a in unit_quat(), b in unit_quat(), t in 0.0f32..1.0) {
+        let b = if a.dot(&b) < 0.0 { -b } else { b };
+        let total = a.dot(&b).min(1.0).acos();
+        prop_assume!(total > 0.05);
+        let q = Quat::slerp(a, b, t);
+        prop_assert!((q.length2() - 1.0).abs() < 1e-4);
+        prop_assert!((a.dot(&q).min(1.0).acos() - t * total).abs() < 1e-3);
+        prop_assert!(same_rotation(Quat::slerp(a, b, 0.0), a));
+        prop_assert!(same_rotation(Quat::slerp(a, b, 1.0), b));
+    }
+
+    #[test]
+    fn squad_endpoints(q0 in unit_quat(), q1 in unit_quat(), q2 in unit_quat(), q3 in unit_quat()) {
+        let a = Quat::squad_control(q0, q1, q2);
+        let b = Quat::squad_control(q1, q2, q3);
+        prop_assert!(same_rotation(Quat::squad(q1, q2, a, b, 0.0), q1));
+        prop_assert!(same_rotation(Quat::squad(q1, q2, a, b, 1.0), q2));
+    }
+}
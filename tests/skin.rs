@@ -0,0 +1,81 @@
+use raster::*;
+
+const METHODS: [SkinningMethod; 2] = [SkinningMethod::Linear, SkinningMethod::DualQuaternion];
+
+fn assert_close(a: Vec3, b: Vec3) {
+    assert!((a - b).length2() < 1e-8, "{a:?} != {b:?}");
+}
+
+fn vertexes() -> Vec<Vertex> {
+    [
+        (Vec3::new(0.2, 0.0, 0.1), [0, 0, 0, 0], [1.0, 0.0, 0.0, 0.0]),
+        (
+            Vec3::new(-0.1, 1.0, 0.3),
+            [0, 1, 0, 0],
+            [0.5, 0.5, 0.0, 0.0],
+        ),
+        (
+            Vec3::new(0.0, 2.0, -0.2),
+            [1, 0, 0, 0],
+            [1.0, 0.0, 0.0, 0.0],
+        ),
+        (Vec3::new(1.0, 1.0, 1.0), [0; 4], [0.0; 4]),
+    ]
+    .into_iter()
+    .map(|(position, joints, weights)| {
+        Vertex::new(position, Vec3::new(0.6, 0.0, 0.8), Vec2::default()).with_skin(joints, weights)
+    })
+    .collect()
+}
+
+//a root joint and a child above it, bound where they stand
+fn skeleton() -> Skeleton {
+    let root = Transform::new(
+        Vec3::new(0.0, 0.5, 0.0),
+        Quat::from_axis_angle(Vec3::Y, 0.7),
+        Vec3::new(1.0, 1.0, 1.0),
+    );
+    let child = Transform::new(
+        Vec3::new(0.0, 1.0, 0.0),
+        Quat::from_axis_angle(Vec3::X, -0.4),
+        Vec3::new(1.0, 1.0, 1.0),
+    );
+    let root_world = root.to_matrix();
+    let child_world = root_world * child.to_matrix();
+    Skeleton::new(vec![
+        Joint::new("root", None, root, root_world.inv().unwrap()),
+        Joint::new("child", Some(0), child, child_world.inv().unwrap()),
+    ])
+}
+
+#[test]
+fn bind_pose_keeps_the_vertexes() {
+    let vertexes = vertexes();
+    for method in METHODS {
+        let skinned = skeleton().with_method(method).skin(&vertexes);
+        for (skinned, vertex) in skinned.iter().zip(vertexes.iter()) {
+            assert_close(skinned.position, vertex.position);
+            assert_close(skinned.normal, vertex.normal);
+        }
+    }
+}
+
+#[test]
+fn rigid_joint_is_the_same_with_both_methods() {
+    let mut skeleton = skeleton();
+    skeleton.joints[0].transform = Transform::new(
+        Vec3::new(1.0, -2.0, 0.5),
+        Quat::from_axis_angle(Vec3::new(1.0, 1.0, 0.0).normalize(), 1.2),
+        Vec3::new(1.0, 1.0, 1.0),
+    );
+    //only the first vertex follows the root joint alone
+    let vertexes = &vertexes()[..1];
+    let expected = skeleton.get_joint_matrices()[0]
+        .mul(vertexes[0].position.upgrade())
+        .perspective_divide();
+    let linear = skeleton.clone().with_method(METHODS[0]).skin(vertexes);
+    let dual_quat = skeleton.with_method(METHODS[1]).skin(vertexes);
+    assert_close(linear[0].position, expected);
+    assert_close(dual_quat[0].position, expected);
+    assert_close(linear[0].normal, dual_quat[0].normal);
+}
@@ -0,0 +1,75 @@
+use proptest::prelude::*;
+use raster::*;
+
+fn vec3(range: std::ops::Range<f32>) -> impl Strategy<Value = Vec3> {
+    (range.clone(), range.clone(), range).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+}
+
+fn unit_quat() -> impl Strategy<Value = Quat> {
+    (-1.0f32..1.0, -1.0f32..1.0, -1.0f32..1.0, -1.0f32..1.0)
+        .prop_filter("zero quat", |(x, y, z, w)| {
+            x * x + y * y + z * z + w * w > 0.01
+        })
+        .prop_map(|(x, y, z, w)| Quat::new(x, y, z, w).normalize())
+}
+
+//q and -q are the same rotation
+fn same_rotation(a: Quat, b: Quat) -> bool {
+    a.dot(&b).abs() > 1.0 - 1e-4
+}
+
+fn close(a: Vec3, b: Vec3) -> bool {
+    (a - b).length2() < 1e-6
+}
+
+fn same_matrix(a: &Matrix4, b: &Matrix4) -> bool {
+    (0..4).all(|row| (0..4).all(|col| (a.get(row, col) - b.get(row, col)).abs() < 1e-3))
+}
+
+proptest! {
+    #[test]
+    fn matrix_round_trip(
+        translation in vec3(-10.0..10.0),
+        rotation in unit_quat(),
+        scale in vec3(0.2..3.0),
+        mirror in prop::bool::ANY,
+    ) {
+        //a mirroring comes back in the x scale
+        let scale = if mirror { Vec3::new(-scale.x, scale.y, scale.z) } else { scale };
+        let transform = Transform::new(translation, rotation, scale);
+        let back = Transform::from_matrix(&transform.to_matrix());
+        prop_assert!(close(back.translation, translation));
+        prop_assert!(same_rotation(back.rotation, rotation));
+        prop_assert!(close(back.scale, scale));
+    }
+
+    #[test]
+    fn points_match_the_matrix(
+        translation in vec3(-10.0..10.0),
+        rotation in unit_quat(),
+        scale in vec3(0.2..3.0),
+        point in vec3(-1.0..1.0),
+    ) {
+        let transform = Transform::new(translation, rotation, scale);
+        let expected = transform.to_matrix().mul(point.upgrade()).perspective_divide();
+        prop_assert!(close(transform.transform_point(point), expected));
+    }
+
+    #[test]
+    fn inverse_of_uniform_scale(
+        translation in vec3(-10.0..10.0),
+        rotation in unit_quat(),
+        scale in 0.2f32..3.0,
+    ) {
+        let transform = Transform::new(translation, rotation, Vec3::new(scale, scale, scale));
+        for identity in [transform * transform.inverse(), transform.inverse() * transform] {
+            prop_assert!(close(identity.translation, Vec3::zero()));
+            prop_assert!(same_rotation(identity.rotation, Quat::identity()));
+            prop_assert!(close(identity.scale, Vec3::new(1.0, 1.0, 1.0)));
+        }
+        prop_assert!(same_matrix(
+            &transform.inverse().to_matrix(),
+            &transform.to_matrix().inv().unwrap(),
+        ));
+    }
+}